@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+
+#[cfg(feature = "indexmap")]
+use indexmap::IndexMap;
+use josekit::{
+    jwe::{JweDecrypter, JweEncrypter},
+    jws::{JwsSigner, JwsVerifier},
+    jwt::JwtPayload,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    error::Error,
+    jwt::{decrypt_checked, sign_and_encrypt_payload, verify_nested_payload},
+    options::{SignOptions, VerificationOptions},
+};
+
+// Attribute tokens
+//
+
+fn sign_and_encrypt_attributes<T: Serialize>(
+    attributes: &T,
+    signer: &dyn JwsSigner,
+    encrypter: &dyn JweEncrypter,
+) -> Result<String, Error> {
+    let mut sig_payload = JwtPayload::new();
+    sig_payload.set_claim("attributes", Some(serde_json::to_value(attributes)?))?;
+
+    sign_and_encrypt_payload(sig_payload, signer, encrypter, &SignOptions::default())
+}
+
+fn decrypt_and_verify_attributes<T: DeserializeOwned>(
+    jwe: &str,
+    validator: &dyn JwsVerifier,
+    decrypter: &dyn JweDecrypter,
+    options: &VerificationOptions,
+) -> Result<T, Error> {
+    let jws = decrypt_checked(jwe, decrypter, options)?;
+    let (decoded_jws, _) = verify_nested_payload(&jws, validator, true, options)?;
+    if decoded_jws.claim("status").is_some() {
+        return Err(Error::WrongTokenType);
+    }
+    let attributes = decoded_jws
+        .claim("attributes")
+        .ok_or(Error::MissingClaim("attributes"))?;
+    serde_json::from_value::<T>(attributes.clone())
+        .map_err(|_| Error::InvalidClaimType("attributes"))
+}
+
+/// Sign and encrypt a given set of attributes, whose values may be arbitrary
+/// json values rather than just strings.
+pub fn sign_and_encrypt_attributes_json(
+    attributes: &HashMap<String, Value>,
+    signer: &dyn JwsSigner,
+    encrypter: &dyn JweEncrypter,
+) -> Result<String, Error> {
+    sign_and_encrypt_attributes(attributes, signer, encrypter)
+}
+
+/// Decrypt and verify a given jwe to extract the contained attributes, keeping
+/// their values as json values.
+pub fn decrypt_and_verify_attributes_json(
+    jwe: &str,
+    validator: &dyn JwsVerifier,
+    decrypter: &dyn JweDecrypter,
+) -> Result<HashMap<String, Value>, Error> {
+    decrypt_and_verify_attributes(jwe, validator, decrypter, &VerificationOptions::default())
+}
+
+/// Decrypt and verify a given jwe to extract the contained attributes, keeping
+/// their values as json values, using the given verification options.
+pub fn decrypt_and_verify_attributes_json_with_options(
+    jwe: &str,
+    validator: &dyn JwsVerifier,
+    decrypter: &dyn JweDecrypter,
+    options: &VerificationOptions,
+) -> Result<HashMap<String, Value>, Error> {
+    decrypt_and_verify_attributes(jwe, validator, decrypter, options)
+}
+
+/// Sign and encrypt the attributes produced by an iterator, without first
+/// collecting them into a separate map. Later values replace earlier ones with
+/// the same name.
+pub fn sign_and_encrypt_attributes_from_iter(
+    attributes: impl IntoIterator<Item = (String, String)>,
+    signer: &dyn JwsSigner,
+    encrypter: &dyn JweEncrypter,
+) -> Result<String, Error> {
+    let attributes: Map<String, Value> = attributes
+        .into_iter()
+        .map(|(name, value)| (name, Value::String(value)))
+        .collect();
+    let mut sig_payload = JwtPayload::new();
+    sig_payload.set_claim("attributes", Some(Value::Object(attributes)))?;
+
+    sign_and_encrypt_payload(sig_payload, signer, encrypter, &SignOptions::default())
+}
+
+/// Sign and encrypt a given set of attributes, keeping them in the order of
+/// the map.
+#[cfg(feature = "indexmap")]
+pub fn sign_and_encrypt_attributes_ordered(
+    attributes: &IndexMap<String, String>,
+    signer: &dyn JwsSigner,
+    encrypter: &dyn JweEncrypter,
+) -> Result<String, Error> {
+    sign_and_encrypt_attributes(attributes, signer, encrypter)
+}
+
+/// Decrypt and verify a given jwe to extract the contained attributes, in the
+/// order in which they were encoded.
+#[cfg(feature = "indexmap")]
+pub fn decrypt_and_verify_attributes_ordered(
+    jwe: &str,
+    validator: &dyn JwsVerifier,
+    decrypter: &dyn JweDecrypter,
+) -> Result<IndexMap<String, String>, Error> {
+    decrypt_and_verify_attributes(jwe, validator, decrypter, &VerificationOptions::default())
+}
+
+/// Decrypt and verify a given jwe to extract the contained attributes, in the
+/// order in which they were encoded, using the given verification options.
+#[cfg(feature = "indexmap")]
+pub fn decrypt_and_verify_attributes_ordered_with_options(
+    jwe: &str,
+    validator: &dyn JwsVerifier,
+    decrypter: &dyn JweDecrypter,
+    options: &VerificationOptions,
+) -> Result<IndexMap<String, String>, Error> {
+    decrypt_and_verify_attributes(jwe, validator, decrypter, options)
+}
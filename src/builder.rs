@@ -0,0 +1,179 @@
+use std::time::{Duration, SystemTime};
+
+use josekit::{jwe::JweEncrypter, jws::JwsSigner};
+use serde_json::Value;
+use verder_helpen_proto::AuthResult;
+
+use crate::{
+    error::Error,
+    jwt::sign_and_encrypt_auth_result_with_options,
+    options::{Serialization, SignOptions},
+    status::SuccessSpelling,
+};
+
+// Signing builder
+//
+
+/// Builder for assembling the parameters used when signing and encrypting an
+/// auth result. Parameters not explicitly set keep their default values.
+#[derive(Debug, Clone, Default)]
+pub struct SignEncryptBuilder {
+    options: SignOptions,
+}
+
+impl SignEncryptBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the amount of time for which the token is valid.
+    pub fn lifetime(mut self, lifetime: Duration) -> Self {
+        self.options.lifetime = lifetime;
+        self
+    }
+
+    /// Set the audience (`aud`) the token is intended for.
+    pub fn audience(mut self, audience: impl Into<String>) -> Self {
+        self.options.audience = Some(audience.into());
+        self
+    }
+
+    /// Set the issuer (`iss`) of the token.
+    pub fn issuer(mut self, issuer: impl Into<String>) -> Self {
+        self.options.issuer = Some(issuer.into());
+        self
+    }
+
+    /// Set the key id (`kid`) placed in the jws header, overriding that of the
+    /// signer.
+    pub fn kid(mut self, kid: impl Into<String>) -> Self {
+        self.options.kid = Some(kid.into());
+        self
+    }
+
+    /// Set the content encryption algorithm (`enc`) used for the outer jwe.
+    pub fn content_encryption(mut self, content_encryption: impl Into<String>) -> Self {
+        self.options.content_encryption = content_encryption.into();
+        self
+    }
+
+    /// Set the moment (`nbf`) before which the token is not valid.
+    pub fn not_before(mut self, not_before: SystemTime) -> Self {
+        self.options.not_before = Some(not_before);
+        self
+    }
+
+    /// Set whether the nested jws is compressed using DEFLATE before
+    /// encrypting. Defaults to off.
+    pub fn compress(mut self, compress: bool) -> Self {
+        self.options.compress = compress;
+        self
+    }
+
+    /// Set the size in bytes the nested jws must exceed to be compressed.
+    /// Defaults to 1024 bytes, so that small tokens are left uncompressed.
+    pub fn compression_threshold(mut self, compression_threshold: usize) -> Self {
+        self.options.compression_threshold = compression_threshold;
+        self
+    }
+
+    /// Add an additional claim to the signed payload. Claims set by this
+    /// library itself are rejected when signing.
+    pub fn claim(mut self, name: impl Into<String>, value: Value) -> Self {
+        self.options.extra_claims.insert(name.into(), value);
+        self
+    }
+
+    /// Set the serialization used for the outer jwe.
+    pub fn serialization(mut self, serialization: Serialization) -> Self {
+        self.options.serialization = serialization;
+        self
+    }
+
+    /// Set the moment the token is issued (`iat`), instead of the current
+    /// time. Its expiry is computed from this moment.
+    pub fn issued_at(mut self, issued_at: SystemTime) -> Self {
+        self.options.issued_at = Some(issued_at);
+        self
+    }
+
+    /// Set the unique id (`jti`) of the token.
+    pub fn jwt_id(mut self, jwt_id: impl Into<String>) -> Self {
+        self.options.jwt_id = Some(jwt_id.into());
+        self
+    }
+
+    /// Give each token signed with this builder a random uuid as id (`jti`),
+    /// unless an explicit id is set.
+    #[cfg(feature = "uuid")]
+    pub fn random_jwt_id(mut self) -> Self {
+        self.options.random_jwt_id = true;
+        self
+    }
+
+    /// Set the token type (`typ`) placed in the jwe header. Defaults to `JWT`.
+    pub fn token_type(mut self, token_type: impl Into<String>) -> Self {
+        self.options.token_type = token_type.into();
+        self
+    }
+
+    /// Set the content type (`cty`) placed in the jwe header. Defaults to
+    /// `JWT`.
+    pub fn content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.options.content_type = content_type.into();
+        self
+    }
+
+    /// Set the name of the claim of the outer jwe holding the nested jws.
+    /// Defaults to `njwt`.
+    pub fn nested_claim(mut self, nested_claim: impl Into<String>) -> Self {
+        self.options.nested_claim = nested_claim.into();
+        self
+    }
+
+    /// Set the spelling of the successful status, for recipients that only
+    /// know the old `Succes` spelling.
+    pub fn success_spelling(mut self, success_spelling: SuccessSpelling) -> Self {
+        self.options.success_spelling = success_spelling;
+        self
+    }
+
+    /// Set the challenge (`nonce`) echoed in the token.
+    pub fn nonce(mut self, nonce: impl Into<String>) -> Self {
+        self.options.nonce = Some(nonce.into());
+        self
+    }
+
+    /// Set the status of an individual attribute, such as whether it was
+    /// verified.
+    pub fn attribute_status(mut self, name: impl Into<String>, status: impl Into<String>) -> Self {
+        self.options
+            .attribute_status
+            .insert(name.into(), status.into());
+        self
+    }
+
+    /// Add an additional parameter to the protected jwe header. The header is
+    /// readable without decrypting, so it must not hold sensitive values.
+    /// Registered parameters, such as `alg` or `crit`, are rejected when
+    /// encrypting.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.extra_headers.insert(name.into(), value.into());
+        self
+    }
+
+    /// The options assembled so far.
+    pub fn options(&self) -> &SignOptions {
+        &self.options
+    }
+
+    /// Sign and encrypt a given auth result using the assembled parameters.
+    pub fn build_and_sign(
+        &self,
+        auth_result: &AuthResult,
+        signer: &dyn JwsSigner,
+        encrypter: &dyn JweEncrypter,
+    ) -> Result<String, Error> {
+        sign_and_encrypt_auth_result_with_options(auth_result, signer, encrypter, &self.options)
+    }
+}
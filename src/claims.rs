@@ -0,0 +1,108 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime},
+};
+
+use josekit::{jws::JwsHeader, jwt::JwtPayload};
+use serde_json::{Map, Value};
+
+use crate::error::Error;
+
+// Verified claims
+//
+
+/// Claims set by this library itself, which cannot be given as extra claims.
+pub(crate) const RESERVED_CLAIMS: &[&str] = &[
+    "iss",
+    "sub",
+    "aud",
+    "exp",
+    "nbf",
+    "iat",
+    "jti",
+    "nonce",
+    "attribute_status",
+    "status",
+    "attributes",
+    "session_url",
+];
+
+// Read a numeric time claim. Values too large to represent are rejected here,
+// as josekit's own accessors panic on them.
+pub(crate) fn time_claim(
+    payload: &JwtPayload,
+    name: &'static str,
+) -> Result<Option<SystemTime>, Error> {
+    match payload.claim(name) {
+        None => Ok(None),
+        Some(value) => value
+            .as_u64()
+            .and_then(|secs| SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(secs)))
+            .map(Some)
+            .ok_or(Error::InvalidClaimType(name)),
+    }
+}
+
+/// Standard claims of a token whose signature and claims have been verified.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifiedClaims {
+    /// Moment the token was issued (`iat`), if present.
+    pub issued_at: Option<SystemTime>,
+    /// Moment the token expires (`exp`).
+    pub expires_at: SystemTime,
+    /// Moment before which the token is not valid (`nbf`), if present.
+    pub not_before: Option<SystemTime>,
+    /// Issuer (`iss`) of the token, if present.
+    pub issuer: Option<String>,
+    /// Audience (`aud`) of the token, empty when not present.
+    pub audience: Vec<String>,
+    /// Subject (`sub`) of the token, if present.
+    pub subject: Option<String>,
+    /// Unique id (`jti`) of the token, if present.
+    pub jwt_id: Option<String>,
+    /// Challenge (`nonce`) echoed in the token, if present.
+    pub nonce: Option<String>,
+    /// Status of individual attributes (`attribute_status`), empty when not
+    /// present.
+    pub attribute_status: HashMap<String, String>,
+    /// Claims other than the reserved ones, such as extra claims given when
+    /// signing.
+    pub extra: Map<String, Value>,
+    /// Key id (`kid`) from the header of the nested jws, if present.
+    pub kid: Option<String>,
+}
+
+impl VerifiedClaims {
+    pub(crate) fn from_parts(payload: &JwtPayload, header: &JwsHeader) -> Result<Self, Error> {
+        Ok(VerifiedClaims {
+            issued_at: time_claim(payload, "iat")?,
+            expires_at: time_claim(payload, "exp")?.ok_or(Error::MissingClaim("exp"))?,
+            not_before: time_claim(payload, "nbf")?,
+            issuer: payload.issuer().map(str::to_string),
+            audience: payload
+                .audience()
+                .unwrap_or_default()
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+            subject: payload.subject().map(str::to_string),
+            jwt_id: payload.jwt_id().map(str::to_string),
+            nonce: payload
+                .claim("nonce")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            attribute_status: match payload.claim("attribute_status") {
+                Some(attribute_status) => serde_json::from_value(attribute_status.clone())
+                    .map_err(|_| Error::InvalidClaimType("attribute_status"))?,
+                None => HashMap::new(),
+            },
+            extra: payload
+                .claims_set()
+                .iter()
+                .filter(|(name, _)| !RESERVED_CLAIMS.contains(&name.as_str()))
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
+            kid: header.key_id().map(str::to_string),
+        })
+    }
+}
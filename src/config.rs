@@ -1,18 +1,567 @@
-use std::{convert::TryFrom, fmt::Debug};
+use std::{borrow::Cow, convert::TryFrom, fmt::Debug, path::PathBuf, sync::Arc};
 
+#[cfg(feature = "rsa")]
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::{engine::general_purpose::STANDARD, Engine};
 use josekit::{
-    jwe::{JweDecrypter, JweEncrypter, ECDH_ES, RSA_OAEP},
-    jws::{JwsSigner, JwsVerifier, ES256, RS256},
+    jwe::{
+        Dir, JweAlgorithm, JweContentEncryption, JweDecrypter, JweEncrypter, JweHeader,
+        JweHeaderSet, A128KW, A192KW, A256KW,
+    },
+    jwk::{alg::ed::EdKeyPair, Jwk},
+    jws::{EdDSA, JwsSigner, JwsVerifier, HS256, HS384, HS512},
+    JoseError,
 };
+#[cfg(feature = "ec")]
+use josekit::{
+    jwe::{ECDH_ES, ECDH_ES_A128KW, ECDH_ES_A192KW, ECDH_ES_A256KW},
+    jwk::alg::ec::EcKeyPair,
+    jws::{ES256, ES256K, ES384, ES512},
+};
+#[cfg(feature = "rsa")]
+use josekit::{
+    jwe::{RSA_OAEP, RSA_OAEP_256},
+    jwk::alg::rsa::RsaKeyPair,
+    jws::{PS256, PS384, PS512, RS256},
+};
+use openssl::pkey::PKey;
+#[cfg(feature = "rsa")]
+use openssl::rsa::Rsa;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
 use crate::error::Error;
 
 // Configuration management
 //
-#[derive(Serialize, Deserialize)]
+
+// With the zeroize feature enabled, PEM and symmetric key material is wiped
+// from memory once it is dropped.
+#[cfg(feature = "zeroize")]
+type KeyString = zeroize::Zeroizing<String>;
+#[cfg(not(feature = "zeroize"))]
+type KeyString = String;
+
+#[cfg(feature = "zeroize")]
+type KeyBytes = zeroize::Zeroizing<Vec<u8>>;
+#[cfg(not(feature = "zeroize"))]
+type KeyBytes = Vec<u8>;
+
+fn read_key_file(path: PathBuf) -> Result<KeyBytes, Error> {
+    Ok(KeyBytes::from(std::fs::read(path)?))
+}
+
+fn decode_key(key: &KeyString) -> Result<KeyBytes, Error> {
+    Ok(KeyBytes::from(STANDARD.decode(key.as_bytes()).map_err(
+        |_| Error::InvalidKey("key is not valid base64"),
+    )?))
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged, try_from = "RawKeyMaterial")]
+enum KeyMaterial {
+    Pem { key: KeyString },
+    Jwk { jwk: Map<String, Value> },
+    PemFile { key_file: PathBuf },
+}
+
+// Key material as given in the configuration. Exactly one of its fields must be
+// set, so that a leftover inline key cannot silently take precedence over a
+// key file or the other way around.
+#[derive(Deserialize)]
+struct RawKeyMaterial {
+    key: Option<KeyString>,
+    jwk: Option<Map<String, Value>>,
+    key_file: Option<PathBuf>,
+}
+
+impl TryFrom<RawKeyMaterial> for KeyMaterial {
+    type Error = Error;
+
+    fn try_from(raw: RawKeyMaterial) -> Result<Self, Error> {
+        match (raw.key, raw.jwk, raw.key_file) {
+            (Some(key), None, None) => Ok(KeyMaterial::Pem { key }),
+            (None, Some(jwk), None) => Ok(KeyMaterial::Jwk { jwk }),
+            (None, None, Some(key_file)) => Ok(KeyMaterial::PemFile { key_file }),
+            (None, None, None) => Err(Error::InvalidKey(
+                "one of key, jwk and key_file must be given",
+            )),
+            _ => Err(Error::InvalidKey(
+                "only one of key, jwk and key_file may be given",
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum KeyEncoding {
+    Pem,
+    Der,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct InnerKeyConfig {
-    key: String,
+    #[serde(flatten)]
+    material: KeyMaterial,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    encoding: Option<KeyEncoding>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    alg: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kid: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    min_key_bits: Option<usize>,
+}
+
+// Construct and box a josekit key from the configured key material, using the
+// given algorithm and constructors. DER encoded keys are given base64 encoded
+// in `key`, or as raw bytes in `key_file`. The configured key id (if any) is
+// attached so that it ends up in the headers of produced tokens.
+macro_rules! load_key {
+    ($alg:expr, $config:ident, $from_pem:ident, $from_der:ident, $from_jwk:ident) => {{
+        let der = matches!($config.encoding, Some(KeyEncoding::Der));
+        let mut loaded = match $config.material {
+            KeyMaterial::Pem { key } if der => $alg.$from_der(decode_key(&key)?.as_slice())?,
+            KeyMaterial::Pem { key: pem } => $alg.$from_pem(pem.as_bytes())?,
+            KeyMaterial::Jwk { jwk } => $alg.$from_jwk(&Jwk::from_map(jwk)?)?,
+            KeyMaterial::PemFile { key_file } if der => {
+                $alg.$from_der(read_key_file(key_file)?.as_slice())?
+            }
+            KeyMaterial::PemFile { key_file } => {
+                $alg.$from_pem(read_key_file(key_file)?.as_slice())?
+            }
+        };
+        if let Some(kid) = $config.kid {
+            loaded.set_key_id(kid);
+        }
+        Box::new(loaded)
+    }};
+}
+
+// Construct and box a josekit key from a symmetric key configuration, using
+// the key management algorithm selected by its `alg` field.
+macro_rules! load_symmetric_key {
+    ($config:ident, $from_bytes:ident) => {{
+        let bytes = $config.key_bytes()?;
+        let kid = $config.kid;
+        match $config.alg.as_deref() {
+            Some("A128KW") => load_symmetric_key!(A128KW, bytes, kid, $from_bytes),
+            Some("A192KW") => load_symmetric_key!(A192KW, bytes, kid, $from_bytes),
+            Some("A256KW") => load_symmetric_key!(A256KW, bytes, kid, $from_bytes),
+            _ => load_symmetric_key!(Dir, bytes, kid, $from_bytes),
+        }
+    }};
+    ($alg:expr, $bytes:ident, $kid:ident, $from_bytes:ident) => {{
+        let mut loaded = $alg.$from_bytes($bytes.as_slice())?;
+        if let Some(kid) = $kid {
+            loaded.set_key_id(kid);
+        }
+        Box::new(loaded)
+    }};
+}
+
+/// Configuration of a shared symmetric key, given base64 encoded in `key`. The
+/// optional `alg` field selects the key management algorithm: `dir` (the
+/// default) uses the key directly, while `A128KW`, `A192KW` and `A256KW` wrap a
+/// random content key with it. For `dir`, the optional `enc` field names the
+/// content encryption algorithm the key is used with, against which the key
+/// length is checked; producing tokens with any other content encryption then
+/// fails. Without `enc`, the key must be 32 bytes, fitting the default
+/// `A128CBC-HS256` as well as `A256GCM`. For key wrapping, the key length must
+/// match the algorithm instead, and `enc` is rejected, as the content key is
+/// random and tokens can be produced with any content encryption.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct SymmetricKeyConfig {
+    key: KeyString,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    alg: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    enc: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kid: Option<String>,
+}
+
+impl SymmetricKeyConfig {
+    // Decode the key, checking that its length fits the key wrapping algorithm
+    // or, for direct encryption, the content encryption.
+    fn key_bytes(&self) -> Result<KeyBytes, Error> {
+        let (key_len, mismatch) = match self.alg.as_deref() {
+            None | Some("dir") => {
+                let key_len = match self.enc.as_deref().unwrap_or("A128CBC-HS256") {
+                    "A128GCM" => 16,
+                    "A192GCM" => 24,
+                    "A256GCM" | "A128CBC-HS256" => 32,
+                    "A192CBC-HS384" => 48,
+                    "A256CBC-HS512" => 64,
+                    enc => return Err(Error::UnsupportedAlgorithm(enc.to_string())),
+                };
+                (key_len, "key length does not match content encryption")
+            }
+            Some("A128KW" | "A192KW" | "A256KW") if self.enc.is_some() => {
+                return Err(Error::InvalidKey(
+                    "content encryption cannot be configured with key wrapping",
+                ))
+            }
+            Some("A128KW") => (16, "key length does not match key wrapping algorithm"),
+            Some("A192KW") => (24, "key length does not match key wrapping algorithm"),
+            Some("A256KW") => (32, "key length does not match key wrapping algorithm"),
+            Some(alg) => return Err(Error::UnsupportedAlgorithm(alg.to_string())),
+        };
+        let key = decode_key(&self.key)?;
+        if key.len() != key_len {
+            return Err(Error::InvalidKey(mismatch));
+        }
+        Ok(key)
+    }
+}
+
+impl Debug for SymmetricKeyConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SymmetricKeyConfig").finish()
+    }
+}
+
+// Encrypter for a symmetric key configured with a content encryption, refusing
+// to produce tokens with any other content encryption rather than silently
+// using the key for it.
+#[derive(Debug)]
+struct ContentEncryptionBound {
+    inner: Box<dyn JweEncrypter>,
+    enc: String,
+}
+
+impl JweEncrypter for ContentEncryptionBound {
+    fn algorithm(&self) -> &dyn JweAlgorithm {
+        self.inner.algorithm()
+    }
+
+    fn key_id(&self) -> Option<&str> {
+        self.inner.key_id()
+    }
+
+    fn compute_content_encryption_key(
+        &self,
+        cencryption: &dyn JweContentEncryption,
+        in_header: &JweHeader,
+        out_header: &mut JweHeaderSet,
+    ) -> Result<Option<Cow<[u8]>>, JoseError> {
+        if cencryption.name() != self.enc {
+            return Err(JoseError::InvalidKeyFormat(anyhow::anyhow!(
+                "key is configured for content encryption {}, not {}",
+                self.enc,
+                cencryption.name()
+            )));
+        }
+        self.inner
+            .compute_content_encryption_key(cencryption, in_header, out_header)
+    }
+
+    fn encrypt(
+        &self,
+        key: &[u8],
+        in_header: &JweHeader,
+        out_header: &mut JweHeaderSet,
+    ) -> Result<Option<Vec<u8>>, JoseError> {
+        self.inner.encrypt(key, in_header, out_header)
+    }
+
+    fn box_clone(&self) -> Box<dyn JweEncrypter> {
+        Box::new(ContentEncryptionBound {
+            inner: self.inner.box_clone(),
+            enc: self.enc.clone(),
+        })
+    }
+}
+
+/// Configuration of a shared HMAC secret, given base64 encoded in `key`. The
+/// optional `alg` field selects `HS256` (the default), `HS384` or `HS512`.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct SecretKeyConfig {
+    key: KeyString,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    alg: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kid: Option<String>,
+}
+
+// Construct and box an HMAC signer or verifier from a secret key configuration.
+// Both need the secret itself, so the same configuration serves for signing and
+// verifying.
+macro_rules! load_secret {
+    ($config:ident, $from_bytes:ident) => {{
+        let key = decode_key(&$config.key)?;
+        let mut loaded = match $config.alg.as_deref() {
+            None | Some("HS256") => HS256.$from_bytes(key.as_slice())?,
+            Some("HS384") => HS384.$from_bytes(key.as_slice())?,
+            Some("HS512") => HS512.$from_bytes(key.as_slice())?,
+            Some(alg) => return Err(Error::UnsupportedAlgorithm(alg.to_string())),
+        };
+        if let Some(kid) = $config.kid {
+            loaded.set_key_id(kid);
+        }
+        Ok(Box::new(loaded))
+    }};
+}
+
+impl Debug for SecretKeyConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SecretKeyConfig").finish()
+    }
+}
+
+// DER encoded object identifiers of the key types, as found in the algorithm
+// identifier of PKCS#8 and SubjectPublicKeyInfo structures.
+const RSA_OID: &[u8] = &[
+    0x06, 0x09, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01,
+];
+const RSA_PSS_OID: &[u8] = &[
+    0x06, 0x09, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0a,
+];
+const EC_OID: &[u8] = &[0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+const ED25519_OID: &[u8] = &[0x06, 0x03, 0x2b, 0x65, 0x70];
+// Object identifier of the secp256k1 curve, found in the parameters of EC keys.
+#[cfg(feature = "ec")]
+const SECP256K1_OID: &[u8] = &[0x06, 0x05, 0x2b, 0x81, 0x04, 0x00, 0x0a];
+
+fn contains_oid(der: &[u8], oid: &[u8]) -> bool {
+    der.windows(oid.len()).any(|window| window == oid)
+}
+
+// Determine the type of a DER encoded key, if recognizable.
+fn der_key_type(der: &[u8]) -> Option<&'static str> {
+    let contains = |oid: &[u8]| contains_oid(der, oid);
+    if contains(RSA_OID) || contains(RSA_PSS_OID) {
+        Some("RSA")
+    } else if contains(EC_OID) {
+        Some("EC")
+    } else if contains(ED25519_OID) {
+        Some("OKP")
+    } else {
+        None
+    }
+}
+
+// Split a PEM document into its label and decoded contents.
+fn pem_der(pem: &[u8]) -> Option<(&str, Vec<u8>)> {
+    let pem = std::str::from_utf8(pem).ok()?;
+    let label = pem.trim_start().strip_prefix("-----BEGIN ")?;
+    let (label, rest) = label.split_once("-----")?;
+    let body: String = rest
+        .split("-----END")
+        .next()?
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    Some((label, STANDARD.decode(body).ok()?))
+}
+
+// Determine the type of the key in a PEM document, if recognizable.
+fn pem_key_type(pem: &[u8]) -> Option<&'static str> {
+    match pem_der(pem)? {
+        ("RSA PRIVATE KEY" | "RSA PUBLIC KEY", _) => Some("RSA"),
+        ("EC PRIVATE KEY", _) => Some("EC"),
+        (_, der) => der_key_type(&der),
+    }
+}
+
+// Determine whether a PEM document holds a private key, from its label.
+fn pem_is_private(pem: &[u8]) -> bool {
+    matches!(pem_der(pem), Some((label, _)) if label.ends_with("PRIVATE KEY"))
+}
+
+// Determine whether a DER encoded key is a private key.
+fn der_is_private(der: &[u8]) -> bool {
+    PKey::private_key_from_der(der).is_ok()
+}
+
+/// Minimum modulus size in bits of RSA keys, unless configured otherwise.
+#[cfg(feature = "rsa")]
+pub const DEFAULT_MIN_RSA_KEY_BITS: usize = 2048;
+
+// Determine the modulus size of an RSA key in PEM format, private or public.
+#[cfg(feature = "rsa")]
+fn rsa_pem_key_bits(pem: &[u8]) -> Option<usize> {
+    PKey::private_key_from_pem(pem)
+        .map(|key| key.bits())
+        .or_else(|_| PKey::public_key_from_pem(pem).map(|key| key.bits()))
+        .or_else(|_| Rsa::public_key_from_pem_pkcs1(pem).map(|key| key.size() * 8))
+        .ok()
+        .map(|bits| bits as usize)
+}
+
+// Determine the modulus size of an RSA key in DER format, private or public.
+#[cfg(feature = "rsa")]
+fn rsa_der_key_bits(der: &[u8]) -> Option<usize> {
+    PKey::private_key_from_der(der)
+        .map(|key| key.bits())
+        .or_else(|_| PKey::public_key_from_der(der).map(|key| key.bits()))
+        .or_else(|_| Rsa::public_key_from_der_pkcs1(der).map(|key| key.size() * 8))
+        .ok()
+        .map(|bits| bits as usize)
+}
+
+// Determine the modulus size of an RSA key in JWK format, from its `n`
+// parameter.
+#[cfg(feature = "rsa")]
+fn rsa_jwk_key_bits(jwk: &Map<String, Value>) -> Option<usize> {
+    let modulus = URL_SAFE_NO_PAD.decode(jwk.get("n")?.as_str()?).ok()?;
+    let modulus = match modulus.iter().position(|&byte| byte != 0) {
+        Some(start) => &modulus[start..],
+        None => return Some(0),
+    };
+    Some(modulus.len() * 8 - modulus[0].leading_zeros() as usize)
+}
+
+impl InnerKeyConfig {
+    // Read a PEM key from an environment variable, given either as is or base64
+    // encoded, together with its type if recognizable.
+    fn from_env(var: &str) -> Result<(Option<&'static str>, Self), Error> {
+        let value = match std::env::var(var) {
+            Ok(value) => KeyString::from(value),
+            Err(std::env::VarError::NotPresent) => return Err(Error::MissingEnv(var.to_string())),
+            Err(std::env::VarError::NotUnicode(_)) => {
+                return Err(Error::InvalidKey(
+                    "environment variable is not valid unicode",
+                ))
+            }
+        };
+        let pem = if value.trim_start().starts_with("-----BEGIN") {
+            value
+        } else {
+            let decoded = STANDARD
+                .decode(value.trim())
+                .map_err(|_| Error::InvalidKey("key is not valid base64"))?;
+            KeyString::from(
+                String::from_utf8(decoded)
+                    .map_err(|_| Error::InvalidKey("key is not a PEM document"))?,
+            )
+        };
+        let key_type = pem_key_type(pem.as_bytes());
+        let config = InnerKeyConfig {
+            material: KeyMaterial::Pem { key: pem },
+            encoding: None,
+            alg: None,
+            kid: None,
+            min_key_bits: None,
+        };
+        Ok((key_type, config))
+    }
+
+    // Check that the key material is of the declared type, so that mistakes in
+    // the configuration give a clear error. Key material of unrecognized type
+    // is left for josekit to reject.
+    fn check_key_type(&self, declared: &'static str) -> Result<(), Error> {
+        let der = matches!(self.encoding, Some(KeyEncoding::Der));
+        let actual = match &self.material {
+            KeyMaterial::Pem { key } if der => der_key_type(&decode_key(key)?),
+            KeyMaterial::Pem { key } => pem_key_type(key.as_bytes()),
+            KeyMaterial::Jwk { jwk } => jwk.get("kty").and_then(Value::as_str),
+            KeyMaterial::PemFile { key_file } if der => {
+                der_key_type(&read_key_file(key_file.clone())?)
+            }
+            KeyMaterial::PemFile { key_file } => pem_key_type(&read_key_file(key_file.clone())?),
+        };
+        match actual {
+            Some(actual) if actual != declared => Err(Error::KeyTypeMismatch {
+                declared,
+                actual: actual.to_string(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    // Determine whether the key material holds a private key, so that it can
+    // be validated in the direction it is meant for.
+    fn is_private(&self) -> Result<bool, Error> {
+        let der = matches!(self.encoding, Some(KeyEncoding::Der));
+        Ok(match &self.material {
+            KeyMaterial::Pem { key } if der => der_is_private(&decode_key(key)?),
+            KeyMaterial::Pem { key } => pem_is_private(key.as_bytes()),
+            KeyMaterial::Jwk { jwk } => jwk.contains_key("d"),
+            KeyMaterial::PemFile { key_file } if der => {
+                der_is_private(&read_key_file(key_file.clone())?)
+            }
+            KeyMaterial::PemFile { key_file } => pem_is_private(&read_key_file(key_file.clone())?),
+        })
+    }
+
+    // Check that an RSA key has a modulus of at least the configured minimum
+    // size. Key material that cannot be parsed is left for josekit to reject.
+    #[cfg(feature = "rsa")]
+    fn check_rsa_key_size(&self) -> Result<(), Error> {
+        let minimum = self.min_key_bits.unwrap_or(DEFAULT_MIN_RSA_KEY_BITS);
+        let der = matches!(self.encoding, Some(KeyEncoding::Der));
+        let bits = match &self.material {
+            KeyMaterial::Pem { key } if der => rsa_der_key_bits(&decode_key(key)?),
+            KeyMaterial::Pem { key } => rsa_pem_key_bits(key.as_bytes()),
+            KeyMaterial::Jwk { jwk } => rsa_jwk_key_bits(jwk),
+            KeyMaterial::PemFile { key_file } if der => {
+                rsa_der_key_bits(&read_key_file(key_file.clone())?)
+            }
+            KeyMaterial::PemFile { key_file } => {
+                rsa_pem_key_bits(&read_key_file(key_file.clone())?)
+            }
+        };
+        match bits {
+            Some(bits) if bits < minimum => Err(Error::WeakKey { bits, minimum }),
+            _ => Ok(()),
+        }
+    }
+
+    // Whether the key material is an EC key on the secp256k1 curve, in which
+    // case it is used with ES256K rather than ES256 by default.
+    #[cfg(feature = "ec")]
+    fn is_secp256k1(&self) -> Result<bool, Error> {
+        let der = matches!(self.encoding, Some(KeyEncoding::Der));
+        let in_pem =
+            |pem: &[u8]| pem_der(pem).map_or(false, |(_, der)| contains_oid(&der, SECP256K1_OID));
+        Ok(match &self.material {
+            KeyMaterial::Pem { key } if der => contains_oid(&decode_key(key)?, SECP256K1_OID),
+            KeyMaterial::Pem { key } => in_pem(key.as_bytes()),
+            KeyMaterial::Jwk { jwk } => jwk.get("crv").and_then(Value::as_str) == Some("secp256k1"),
+            KeyMaterial::PemFile { key_file } if der => {
+                contains_oid(&read_key_file(key_file.clone())?, SECP256K1_OID)
+            }
+            KeyMaterial::PemFile { key_file } => in_pem(&read_key_file(key_file.clone())?),
+        })
+    }
+
+    // Signature algorithm used with an EC key when none is configured.
+    #[cfg(feature = "ec")]
+    fn default_ec_alg(&self) -> Result<&'static str, Error> {
+        Ok(if self.is_secp256k1()? {
+            "ES256K"
+        } else {
+            "ES256"
+        })
+    }
+
+    // Construct the public part of the key as JWK. PEM and DER encoded keys
+    // must be private keys, which are parsed using the given constructors.
+    fn public_jwk(
+        &self,
+        alg: &str,
+        from_pem: fn(&[u8]) -> Result<Jwk, JoseError>,
+        from_der: fn(&[u8]) -> Result<Jwk, JoseError>,
+    ) -> Result<Jwk, Error> {
+        let der = matches!(self.encoding, Some(KeyEncoding::Der));
+        let mut jwk = match &self.material {
+            KeyMaterial::Pem { key } if der => from_der(&decode_key(key)?)?,
+            KeyMaterial::Pem { key } => from_pem(key.as_bytes())?,
+            KeyMaterial::Jwk { jwk } => Jwk::from_map(jwk.clone())?.to_public_key()?,
+            KeyMaterial::PemFile { key_file } if der => {
+                from_der(&read_key_file(key_file.clone())?)?
+            }
+            KeyMaterial::PemFile { key_file } => from_pem(&read_key_file(key_file.clone())?)?,
+        };
+        jwk.set_key_use("sig");
+        jwk.set_algorithm(self.alg.as_deref().unwrap_or(alg));
+        if let Some(kid) = &self.kid {
+            jwk.set_key_id(kid);
+        }
+        Ok(jwk)
+    }
 }
 
 impl Debug for InnerKeyConfig {
@@ -21,23 +570,174 @@ impl Debug for InnerKeyConfig {
     }
 }
 
+// Placeholder shown in debug output instead of key configuration
+struct Redacted;
+
+impl Debug for Redacted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
+
 /// Parsable configuration describing an encryption key.
 /// This can be cast (using try_from) into the JweDecryptor en JweEncryptor
-/// types needed by the jwe functions.
-#[derive(Deserialize, Debug)]
+/// types needed by the jwe functions. The optional `alg` field selects the key
+/// management algorithm, which for RSA keys is either `RSA-OAEP` (the default)
+/// or `RSA-OAEP-256`, and for EC keys one of `ECDH-ES` (the default),
+/// `ECDH-ES+A128KW`, `ECDH-ES+A192KW` or `ECDH-ES+A256KW`. The optional `kid`
+/// field sets the key id placed in the jwe header. Key material is given
+/// either as a PEM string in `key`, as a JWK object in `jwk`, or as the path
+/// of a PEM file in `key_file`. With `encoding: der`, the key is DER encoded
+/// instead, given base64 encoded in `key` or as a binary file in `key_file`.
+/// With the `zeroize` feature, PEM key material is zeroized when dropped. RSA
+/// keys with a modulus smaller than `min_key_bits` (2048 by default) are
+/// rejected.
+/// `Symmetric` keys are base64 encoded shared keys used for direct encryption
+/// (`dir`, the default) or AES key wrapping (`A128KW`, `A192KW` or `A256KW`),
+/// as selected by their `alg` field. For direct encryption, their optional
+/// `enc` field is used to check the key length, and producing tokens with any
+/// other content encryption fails.
+/// `RSA` and `EC` keys are only available with the `rsa` and `ec` features
+/// respectively; configurations using a disabled key type fail to parse.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum EncryptionKeyConfig {
+    #[cfg(feature = "rsa")]
     RSA(InnerKeyConfig),
+    #[cfg(feature = "ec")]
     EC(InnerKeyConfig),
+    Symmetric(SymmetricKeyConfig),
+}
+
+impl Debug for EncryptionKeyConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            #[cfg(feature = "rsa")]
+            EncryptionKeyConfig::RSA(_) => "RSA",
+            #[cfg(feature = "ec")]
+            EncryptionKeyConfig::EC(_) => "EC",
+            EncryptionKeyConfig::Symmetric(_) => "Symmetric",
+        };
+        f.debug_tuple(name).field(&Redacted).finish()
+    }
+}
+
+impl EncryptionKeyConfig {
+    /// Construct the configuration of the PEM key in the given environment
+    /// variable, given either as is or base64 encoded. The key type is derived
+    /// from the key itself.
+    pub fn from_env(var: &str) -> Result<Self, Error> {
+        match InnerKeyConfig::from_env(var)? {
+            #[cfg(feature = "rsa")]
+            (Some("RSA"), key) => Ok(EncryptionKeyConfig::RSA(key)),
+            #[cfg(feature = "ec")]
+            (Some("EC"), key) => Ok(EncryptionKeyConfig::EC(key)),
+            _ => Err(Error::InvalidKey("unsupported key type")),
+        }
+    }
+
+    /// Name of the key management algorithm the configured key is used with,
+    /// for instance to log it at startup.
+    pub fn algorithm(&self) -> &str {
+        match self {
+            #[cfg(feature = "rsa")]
+            EncryptionKeyConfig::RSA(key) => key.alg.as_deref().unwrap_or("RSA-OAEP"),
+            #[cfg(feature = "ec")]
+            EncryptionKeyConfig::EC(key) => key.alg.as_deref().unwrap_or("ECDH-ES"),
+            EncryptionKeyConfig::Symmetric(key) => key.alg.as_deref().unwrap_or("dir"),
+        }
+    }
+
+    /// Check that the configured key can be loaded, without using it. Private
+    /// keys are loaded as decrypter, public keys as encrypter.
+    pub fn validate(&self) -> Result<(), Error> {
+        let is_private = match self {
+            #[cfg(feature = "rsa")]
+            EncryptionKeyConfig::RSA(key) => key.is_private()?,
+            #[cfg(feature = "ec")]
+            EncryptionKeyConfig::EC(key) => key.is_private()?,
+            EncryptionKeyConfig::Symmetric(_) => true,
+        };
+        if is_private {
+            Box::<dyn JweDecrypter>::try_from(self.clone()).map(|_| ())
+        } else {
+            Box::<dyn JweEncrypter>::try_from(self.clone()).map(|_| ())
+        }
+    }
+
+    fn check_key_type(&self) -> Result<(), Error> {
+        match self {
+            #[cfg(feature = "rsa")]
+            EncryptionKeyConfig::RSA(key) => {
+                key.check_key_type("RSA")?;
+                key.check_rsa_key_size()
+            }
+            #[cfg(feature = "ec")]
+            EncryptionKeyConfig::EC(key) => key.check_key_type("EC"),
+            EncryptionKeyConfig::Symmetric(_) => Ok(()),
+        }
+    }
 }
 
 impl TryFrom<EncryptionKeyConfig> for Box<dyn JweDecrypter> {
     type Error = Error;
 
     fn try_from(value: EncryptionKeyConfig) -> Result<Box<dyn JweDecrypter>, Error> {
+        value.check_key_type()?;
         match value {
-            EncryptionKeyConfig::RSA(key) => Ok(Box::new(RSA_OAEP.decrypter_from_pem(key.key)?)),
-            EncryptionKeyConfig::EC(key) => Ok(Box::new(ECDH_ES.decrypter_from_pem(key.key)?)),
+            #[cfg(feature = "rsa")]
+            EncryptionKeyConfig::RSA(key) => match key.alg.as_deref() {
+                None | Some("RSA-OAEP") => Ok(load_key!(
+                    RSA_OAEP,
+                    key,
+                    decrypter_from_pem,
+                    decrypter_from_der,
+                    decrypter_from_jwk
+                )),
+                Some("RSA-OAEP-256") => Ok(load_key!(
+                    RSA_OAEP_256,
+                    key,
+                    decrypter_from_pem,
+                    decrypter_from_der,
+                    decrypter_from_jwk
+                )),
+                Some(alg) => Err(Error::UnsupportedAlgorithm(alg.to_string())),
+            },
+            #[cfg(feature = "ec")]
+            EncryptionKeyConfig::EC(key) => match key.alg.as_deref() {
+                None | Some("ECDH-ES") => Ok(load_key!(
+                    ECDH_ES,
+                    key,
+                    decrypter_from_pem,
+                    decrypter_from_der,
+                    decrypter_from_jwk
+                )),
+                Some("ECDH-ES+A128KW") => Ok(load_key!(
+                    ECDH_ES_A128KW,
+                    key,
+                    decrypter_from_pem,
+                    decrypter_from_der,
+                    decrypter_from_jwk
+                )),
+                Some("ECDH-ES+A192KW") => Ok(load_key!(
+                    ECDH_ES_A192KW,
+                    key,
+                    decrypter_from_pem,
+                    decrypter_from_der,
+                    decrypter_from_jwk
+                )),
+                Some("ECDH-ES+A256KW") => Ok(load_key!(
+                    ECDH_ES_A256KW,
+                    key,
+                    decrypter_from_pem,
+                    decrypter_from_der,
+                    decrypter_from_jwk
+                )),
+                Some(alg) => Err(Error::UnsupportedAlgorithm(alg.to_string())),
+            },
+            EncryptionKeyConfig::Symmetric(key) => {
+                Ok(load_symmetric_key!(key, decrypter_from_bytes))
+            }
         }
     }
 }
@@ -46,30 +746,335 @@ impl TryFrom<EncryptionKeyConfig> for Box<dyn JweEncrypter> {
     type Error = Error;
 
     fn try_from(value: EncryptionKeyConfig) -> Result<Box<dyn JweEncrypter>, Error> {
+        value.check_key_type()?;
         match value {
-            EncryptionKeyConfig::RSA(key) => Ok(Box::new(RSA_OAEP.encrypter_from_pem(key.key)?)),
-            EncryptionKeyConfig::EC(key) => Ok(Box::new(ECDH_ES.encrypter_from_pem(key.key)?)),
+            #[cfg(feature = "rsa")]
+            EncryptionKeyConfig::RSA(key) => match key.alg.as_deref() {
+                None | Some("RSA-OAEP") => Ok(load_key!(
+                    RSA_OAEP,
+                    key,
+                    encrypter_from_pem,
+                    encrypter_from_der,
+                    encrypter_from_jwk
+                )),
+                Some("RSA-OAEP-256") => Ok(load_key!(
+                    RSA_OAEP_256,
+                    key,
+                    encrypter_from_pem,
+                    encrypter_from_der,
+                    encrypter_from_jwk
+                )),
+                Some(alg) => Err(Error::UnsupportedAlgorithm(alg.to_string())),
+            },
+            #[cfg(feature = "ec")]
+            EncryptionKeyConfig::EC(key) => match key.alg.as_deref() {
+                None | Some("ECDH-ES") => Ok(load_key!(
+                    ECDH_ES,
+                    key,
+                    encrypter_from_pem,
+                    encrypter_from_der,
+                    encrypter_from_jwk
+                )),
+                Some("ECDH-ES+A128KW") => Ok(load_key!(
+                    ECDH_ES_A128KW,
+                    key,
+                    encrypter_from_pem,
+                    encrypter_from_der,
+                    encrypter_from_jwk
+                )),
+                Some("ECDH-ES+A192KW") => Ok(load_key!(
+                    ECDH_ES_A192KW,
+                    key,
+                    encrypter_from_pem,
+                    encrypter_from_der,
+                    encrypter_from_jwk
+                )),
+                Some("ECDH-ES+A256KW") => Ok(load_key!(
+                    ECDH_ES_A256KW,
+                    key,
+                    encrypter_from_pem,
+                    encrypter_from_der,
+                    encrypter_from_jwk
+                )),
+                Some(alg) => Err(Error::UnsupportedAlgorithm(alg.to_string())),
+            },
+            EncryptionKeyConfig::Symmetric(key) => match key.enc.clone() {
+                Some(enc) => Ok(Box::new(ContentEncryptionBound {
+                    inner: load_symmetric_key!(key, encrypter_from_bytes),
+                    enc,
+                })),
+                None => Ok(load_symmetric_key!(key, encrypter_from_bytes)),
+            },
         }
     }
 }
 
+/// Construct an encrypter for a recipient's public key given as JWK, for
+/// instance one fetched from a JWKS endpoint. The key management algorithm is
+/// taken from the `alg` field of the JWK, or when absent, determined by its
+/// `kty`: `RSA-OAEP` for RSA keys, `ECDH-ES` for EC keys and `dir` for shared
+/// (`oct`) keys. EC keys may also use `ECDH-ES+A128KW`, `ECDH-ES+A192KW` or
+/// `ECDH-ES+A256KW`, and shared keys `A128KW`, `A192KW` or `A256KW`.
+pub fn encrypter_from_jwk(jwk: &Jwk) -> Result<Box<dyn JweEncrypter>, Error> {
+    match (jwk.key_type(), jwk.algorithm()) {
+        #[cfg(feature = "rsa")]
+        ("RSA", None | Some("RSA-OAEP")) => Ok(Box::new(RSA_OAEP.encrypter_from_jwk(jwk)?)),
+        #[cfg(feature = "rsa")]
+        ("RSA", Some("RSA-OAEP-256")) => Ok(Box::new(RSA_OAEP_256.encrypter_from_jwk(jwk)?)),
+        #[cfg(feature = "ec")]
+        ("EC", None | Some("ECDH-ES")) => Ok(Box::new(ECDH_ES.encrypter_from_jwk(jwk)?)),
+        #[cfg(feature = "ec")]
+        ("EC", Some("ECDH-ES+A128KW")) => Ok(Box::new(ECDH_ES_A128KW.encrypter_from_jwk(jwk)?)),
+        #[cfg(feature = "ec")]
+        ("EC", Some("ECDH-ES+A192KW")) => Ok(Box::new(ECDH_ES_A192KW.encrypter_from_jwk(jwk)?)),
+        #[cfg(feature = "ec")]
+        ("EC", Some("ECDH-ES+A256KW")) => Ok(Box::new(ECDH_ES_A256KW.encrypter_from_jwk(jwk)?)),
+        ("oct", None | Some("dir")) => Ok(Box::new(Dir.encrypter_from_jwk(jwk)?)),
+        ("oct", Some("A128KW")) => Ok(Box::new(A128KW.encrypter_from_jwk(jwk)?)),
+        ("oct", Some("A192KW")) => Ok(Box::new(A192KW.encrypter_from_jwk(jwk)?)),
+        ("oct", Some("A256KW")) => Ok(Box::new(A256KW.encrypter_from_jwk(jwk)?)),
+        (_, None) => Err(Error::InvalidKey(
+            "JWK has no alg and its key type has no default algorithm",
+        )),
+        (_, Some(alg)) => Err(Error::UnsupportedAlgorithm(alg.to_string())),
+    }
+}
+
+/// Assemble a JSON Web Key Set from the given public keys, for instance those
+/// produced by [`SignKeyConfig::to_public_jwk`].
+pub fn jwks(keys: impl IntoIterator<Item = Jwk>) -> Value {
+    let keys = keys
+        .into_iter()
+        .map(|jwk| Value::Object(jwk.as_ref().clone()))
+        .collect();
+    let mut jwks = Map::new();
+    jwks.insert("keys".to_string(), Value::Array(keys));
+    Value::Object(jwks)
+}
+
 /// Parsable configuration describing a signature key.
 /// This can be cast (using try_from) into the JwsVerifier and JwsSigner types
-/// needed by the jwe functions.
-#[derive(Serialize, Deserialize, Debug)]
+/// needed by the jwe functions. The optional `alg` field selects the signature
+/// algorithm, which for RSA keys is one of `RS256` (the default), `PS256`,
+/// `PS384` or `PS512`, and for EC keys one of `ES256` (the default), `ES384`,
+/// `ES512` or `ES256K`, matching the curve of the key. EC keys on the secp256k1
+/// curve default to `ES256K`. `OKP` keys are Ed25519 keys used with EdDSA.
+/// `HMAC` keys are base64 encoded shared secrets used with `HS256` (the
+/// default), `HS384` or `HS512`; the same secret is needed to both sign and
+/// verify.
+/// The optional `kid` field sets the key id placed in the jws header. Key
+/// material is given either as a PEM string in `key`, as a JWK object in `jwk`,
+/// or as the path of a PEM file in `key_file`. With `encoding: der`, the key is
+/// DER encoded instead, given base64 encoded in `key` or as a binary file in
+/// `key_file`. With the `zeroize` feature, PEM key material is zeroized when
+/// dropped. RSA keys with a modulus smaller than `min_key_bits` (2048 by
+/// default) are rejected. `RSA` and `EC` keys are only available with the
+/// `rsa` and `ec` features respectively; configurations using a disabled key
+/// type fail to parse.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum SignKeyConfig {
+    #[cfg(feature = "rsa")]
     RSA(InnerKeyConfig),
+    #[cfg(feature = "ec")]
     EC(InnerKeyConfig),
+    OKP(InnerKeyConfig),
+    HMAC(SecretKeyConfig),
+}
+
+impl Debug for SignKeyConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            #[cfg(feature = "rsa")]
+            SignKeyConfig::RSA(_) => "RSA",
+            #[cfg(feature = "ec")]
+            SignKeyConfig::EC(_) => "EC",
+            SignKeyConfig::OKP(_) => "OKP",
+            SignKeyConfig::HMAC(_) => "HMAC",
+        };
+        f.debug_tuple(name).field(&Redacted).finish()
+    }
+}
+
+impl SignKeyConfig {
+    /// Construct the configuration of the PEM key in the given environment
+    /// variable, given either as is or base64 encoded. The key type is derived
+    /// from the key itself.
+    pub fn from_env(var: &str) -> Result<Self, Error> {
+        match InnerKeyConfig::from_env(var)? {
+            #[cfg(feature = "rsa")]
+            (Some("RSA"), key) => Ok(SignKeyConfig::RSA(key)),
+            #[cfg(feature = "ec")]
+            (Some("EC"), key) => Ok(SignKeyConfig::EC(key)),
+            (Some("OKP"), key) => Ok(SignKeyConfig::OKP(key)),
+            _ => Err(Error::InvalidKey("unsupported key type")),
+        }
+    }
+
+    /// Name of the signature algorithm the configured key is used with, for
+    /// instance to log it at startup.
+    pub fn algorithm(&self) -> &str {
+        match self {
+            #[cfg(feature = "rsa")]
+            SignKeyConfig::RSA(key) => key.alg.as_deref().unwrap_or("RS256"),
+            #[cfg(feature = "ec")]
+            SignKeyConfig::EC(key) => match &key.alg {
+                Some(alg) => alg.as_str(),
+                None => key.default_ec_alg().unwrap_or("ES256"),
+            },
+            SignKeyConfig::OKP(key) => key.alg.as_deref().unwrap_or("EdDSA"),
+            SignKeyConfig::HMAC(key) => key.alg.as_deref().unwrap_or("HS256"),
+        }
+    }
+
+    /// Construct the public part of the configured key as JWK, for instance to
+    /// publish it in a JSON Web Key Set. Keys given in PEM or DER encoding must
+    /// be private keys. HMAC secrets have no public part and are rejected.
+    pub fn to_public_jwk(&self) -> Result<Jwk, Error> {
+        self.check_key_type()?;
+        match self {
+            #[cfg(feature = "rsa")]
+            SignKeyConfig::RSA(key) => key.public_jwk(
+                "RS256",
+                |pem| Ok(RsaKeyPair::from_pem(pem)?.to_jwk_public_key()),
+                |der| Ok(RsaKeyPair::from_der(der)?.to_jwk_public_key()),
+            ),
+            #[cfg(feature = "ec")]
+            SignKeyConfig::EC(key) => key.public_jwk(
+                key.default_ec_alg()?,
+                |pem| Ok(EcKeyPair::from_pem(pem, None)?.to_jwk_public_key()),
+                |der| Ok(EcKeyPair::from_der(der, None)?.to_jwk_public_key()),
+            ),
+            SignKeyConfig::OKP(key) => key.public_jwk(
+                "EdDSA",
+                |pem| Ok(EdKeyPair::from_pem(pem)?.to_jwk_public_key()),
+                |der| Ok(EdKeyPair::from_der(der)?.to_jwk_public_key()),
+            ),
+            SignKeyConfig::HMAC(_) => Err(Error::InvalidKey("shared secrets have no public part")),
+        }
+    }
+
+    /// Check that the configured key can be loaded, without using it. Private
+    /// keys are loaded as signer, public keys as verifier.
+    pub fn validate(&self) -> Result<(), Error> {
+        let is_private = match self {
+            #[cfg(feature = "rsa")]
+            SignKeyConfig::RSA(key) => key.is_private()?,
+            #[cfg(feature = "ec")]
+            SignKeyConfig::EC(key) => key.is_private()?,
+            SignKeyConfig::OKP(key) => key.is_private()?,
+            SignKeyConfig::HMAC(_) => true,
+        };
+        if is_private {
+            Box::<dyn JwsSigner>::try_from(self.clone()).map(|_| ())
+        } else {
+            Box::<dyn JwsVerifier>::try_from(self.clone()).map(|_| ())
+        }
+    }
+
+    fn check_key_type(&self) -> Result<(), Error> {
+        match self {
+            #[cfg(feature = "rsa")]
+            SignKeyConfig::RSA(key) => {
+                key.check_key_type("RSA")?;
+                key.check_rsa_key_size()
+            }
+            #[cfg(feature = "ec")]
+            SignKeyConfig::EC(key) => key.check_key_type("EC"),
+            SignKeyConfig::OKP(key) => key.check_key_type("OKP"),
+            SignKeyConfig::HMAC(_) => Ok(()),
+        }
+    }
 }
 
 impl TryFrom<SignKeyConfig> for Box<dyn JwsVerifier> {
     type Error = Error;
 
     fn try_from(value: SignKeyConfig) -> Result<Box<dyn JwsVerifier>, Error> {
+        value.check_key_type()?;
         match value {
-            SignKeyConfig::RSA(key) => Ok(Box::new(RS256.verifier_from_pem(key.key)?)),
-            SignKeyConfig::EC(key) => Ok(Box::new(ES256.verifier_from_pem(key.key)?)),
+            #[cfg(feature = "rsa")]
+            SignKeyConfig::RSA(key) => match key.alg.as_deref() {
+                None | Some("RS256") => Ok(load_key!(
+                    RS256,
+                    key,
+                    verifier_from_pem,
+                    verifier_from_der,
+                    verifier_from_jwk
+                )),
+                Some("PS256") => Ok(load_key!(
+                    PS256,
+                    key,
+                    verifier_from_pem,
+                    verifier_from_der,
+                    verifier_from_jwk
+                )),
+                Some("PS384") => Ok(load_key!(
+                    PS384,
+                    key,
+                    verifier_from_pem,
+                    verifier_from_der,
+                    verifier_from_jwk
+                )),
+                Some("PS512") => Ok(load_key!(
+                    PS512,
+                    key,
+                    verifier_from_pem,
+                    verifier_from_der,
+                    verifier_from_jwk
+                )),
+                Some(alg) => Err(Error::UnsupportedAlgorithm(alg.to_string())),
+            },
+            #[cfg(feature = "ec")]
+            SignKeyConfig::EC(key) => match key.alg.as_deref() {
+                None if key.is_secp256k1()? => Ok(load_key!(
+                    ES256K,
+                    key,
+                    verifier_from_pem,
+                    verifier_from_der,
+                    verifier_from_jwk
+                )),
+                Some("ES256K") => Ok(load_key!(
+                    ES256K,
+                    key,
+                    verifier_from_pem,
+                    verifier_from_der,
+                    verifier_from_jwk
+                )),
+                None | Some("ES256") => Ok(load_key!(
+                    ES256,
+                    key,
+                    verifier_from_pem,
+                    verifier_from_der,
+                    verifier_from_jwk
+                )),
+                Some("ES384") => Ok(load_key!(
+                    ES384,
+                    key,
+                    verifier_from_pem,
+                    verifier_from_der,
+                    verifier_from_jwk
+                )),
+                Some("ES512") => Ok(load_key!(
+                    ES512,
+                    key,
+                    verifier_from_pem,
+                    verifier_from_der,
+                    verifier_from_jwk
+                )),
+                Some(alg) => Err(Error::UnsupportedAlgorithm(alg.to_string())),
+            },
+            SignKeyConfig::OKP(key) => match key.alg.as_deref() {
+                None | Some("EdDSA") => Ok(load_key!(
+                    EdDSA,
+                    key,
+                    verifier_from_pem,
+                    verifier_from_der,
+                    verifier_from_jwk
+                )),
+                Some(alg) => Err(Error::UnsupportedAlgorithm(alg.to_string())),
+            },
+            SignKeyConfig::HMAC(key) => load_secret!(key, verifier_from_bytes),
         }
     }
 }
@@ -78,9 +1083,207 @@ impl TryFrom<SignKeyConfig> for Box<dyn JwsSigner> {
     type Error = Error;
 
     fn try_from(value: SignKeyConfig) -> Result<Box<dyn JwsSigner>, Error> {
+        value.check_key_type()?;
         match value {
-            SignKeyConfig::RSA(key) => Ok(Box::new(RS256.signer_from_pem(key.key)?)),
-            SignKeyConfig::EC(key) => Ok(Box::new(ES256.signer_from_pem(key.key)?)),
+            #[cfg(feature = "rsa")]
+            SignKeyConfig::RSA(key) => match key.alg.as_deref() {
+                None | Some("RS256") => Ok(load_key!(
+                    RS256,
+                    key,
+                    signer_from_pem,
+                    signer_from_der,
+                    signer_from_jwk
+                )),
+                Some("PS256") => Ok(load_key!(
+                    PS256,
+                    key,
+                    signer_from_pem,
+                    signer_from_der,
+                    signer_from_jwk
+                )),
+                Some("PS384") => Ok(load_key!(
+                    PS384,
+                    key,
+                    signer_from_pem,
+                    signer_from_der,
+                    signer_from_jwk
+                )),
+                Some("PS512") => Ok(load_key!(
+                    PS512,
+                    key,
+                    signer_from_pem,
+                    signer_from_der,
+                    signer_from_jwk
+                )),
+                Some(alg) => Err(Error::UnsupportedAlgorithm(alg.to_string())),
+            },
+            #[cfg(feature = "ec")]
+            SignKeyConfig::EC(key) => match key.alg.as_deref() {
+                None if key.is_secp256k1()? => Ok(load_key!(
+                    ES256K,
+                    key,
+                    signer_from_pem,
+                    signer_from_der,
+                    signer_from_jwk
+                )),
+                Some("ES256K") => Ok(load_key!(
+                    ES256K,
+                    key,
+                    signer_from_pem,
+                    signer_from_der,
+                    signer_from_jwk
+                )),
+                None | Some("ES256") => Ok(load_key!(
+                    ES256,
+                    key,
+                    signer_from_pem,
+                    signer_from_der,
+                    signer_from_jwk
+                )),
+                Some("ES384") => Ok(load_key!(
+                    ES384,
+                    key,
+                    signer_from_pem,
+                    signer_from_der,
+                    signer_from_jwk
+                )),
+                Some("ES512") => Ok(load_key!(
+                    ES512,
+                    key,
+                    signer_from_pem,
+                    signer_from_der,
+                    signer_from_jwk
+                )),
+                Some(alg) => Err(Error::UnsupportedAlgorithm(alg.to_string())),
+            },
+            SignKeyConfig::OKP(key) => match key.alg.as_deref() {
+                None | Some("EdDSA") => Ok(load_key!(
+                    EdDSA,
+                    key,
+                    signer_from_pem,
+                    signer_from_der,
+                    signer_from_jwk
+                )),
+                Some(alg) => Err(Error::UnsupportedAlgorithm(alg.to_string())),
+            },
+            SignKeyConfig::HMAC(key) => load_secret!(key, signer_from_bytes),
         }
     }
 }
+
+// Shared keys
+//
+// Keys can also be loaded into an Arc, for instance to share them between
+// threads or cache them in a `KeyRegistry`. The josekit key traits require
+// Send and Sync, so such an Arc can be sent to other threads as is. Loading
+// from a reference leaves the configuration available for later use.
+
+impl TryFrom<SignKeyConfig> for Arc<dyn JwsSigner> {
+    type Error = Error;
+
+    fn try_from(value: SignKeyConfig) -> Result<Arc<dyn JwsSigner>, Error> {
+        Box::<dyn JwsSigner>::try_from(value).map(Arc::from)
+    }
+}
+
+impl TryFrom<SignKeyConfig> for Arc<dyn JwsVerifier> {
+    type Error = Error;
+
+    fn try_from(value: SignKeyConfig) -> Result<Arc<dyn JwsVerifier>, Error> {
+        Box::<dyn JwsVerifier>::try_from(value).map(Arc::from)
+    }
+}
+
+impl TryFrom<EncryptionKeyConfig> for Arc<dyn JweEncrypter> {
+    type Error = Error;
+
+    fn try_from(value: EncryptionKeyConfig) -> Result<Arc<dyn JweEncrypter>, Error> {
+        Box::<dyn JweEncrypter>::try_from(value).map(Arc::from)
+    }
+}
+
+impl TryFrom<EncryptionKeyConfig> for Arc<dyn JweDecrypter> {
+    type Error = Error;
+
+    fn try_from(value: EncryptionKeyConfig) -> Result<Arc<dyn JweDecrypter>, Error> {
+        Box::<dyn JweDecrypter>::try_from(value).map(Arc::from)
+    }
+}
+
+impl TryFrom<&SignKeyConfig> for Arc<dyn JwsSigner> {
+    type Error = Error;
+
+    fn try_from(value: &SignKeyConfig) -> Result<Arc<dyn JwsSigner>, Error> {
+        Arc::try_from(value.clone())
+    }
+}
+
+impl TryFrom<&SignKeyConfig> for Arc<dyn JwsVerifier> {
+    type Error = Error;
+
+    fn try_from(value: &SignKeyConfig) -> Result<Arc<dyn JwsVerifier>, Error> {
+        Arc::try_from(value.clone())
+    }
+}
+
+impl TryFrom<&EncryptionKeyConfig> for Arc<dyn JweEncrypter> {
+    type Error = Error;
+
+    fn try_from(value: &EncryptionKeyConfig) -> Result<Arc<dyn JweEncrypter>, Error> {
+        Arc::try_from(value.clone())
+    }
+}
+
+impl TryFrom<&EncryptionKeyConfig> for Arc<dyn JweDecrypter> {
+    type Error = Error;
+
+    fn try_from(value: &EncryptionKeyConfig) -> Result<Arc<dyn JweDecrypter>, Error> {
+        Arc::try_from(value.clone())
+    }
+}
+
+/// Parsable configuration of both the signature and the encryption keys of a
+/// service, with the private and public key of each in the `signing` and
+/// `encryption` sections. Deserialize into [`Keys`] to obtain the
+/// corresponding signer, verifier, encrypter and decrypter directly.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KeysConfig {
+    pub signing: KeyPairConfig<SignKeyConfig>,
+    pub encryption: KeyPairConfig<EncryptionKeyConfig>,
+}
+
+/// Private and public key configuration of a key pair.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KeyPairConfig<T> {
+    pub private: T,
+    pub public: T,
+}
+
+/// Ready to use keys, constructed from a [`KeysConfig`].
+#[derive(Deserialize)]
+#[serde(try_from = "KeysConfig")]
+pub struct Keys {
+    pub signer: Box<dyn JwsSigner>,
+    pub verifier: Box<dyn JwsVerifier>,
+    pub encrypter: Box<dyn JweEncrypter>,
+    pub decrypter: Box<dyn JweDecrypter>,
+}
+
+impl Debug for Keys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Keys").finish_non_exhaustive()
+    }
+}
+
+impl TryFrom<KeysConfig> for Keys {
+    type Error = Error;
+
+    fn try_from(value: KeysConfig) -> Result<Keys, Error> {
+        Ok(Keys {
+            signer: Box::<dyn JwsSigner>::try_from(value.signing.private)?,
+            verifier: Box::<dyn JwsVerifier>::try_from(value.signing.public)?,
+            encrypter: Box::<dyn JweEncrypter>::try_from(value.encryption.public)?,
+            decrypter: Box::<dyn JweDecrypter>::try_from(value.encryption.private)?,
+        })
+    }
+}
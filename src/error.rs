@@ -1,5 +1,7 @@
 use std::{error::Error as StdError, fmt::Display};
 
+use verder_helpen_proto::AuthStatus;
+
 // Error type definition
 //
 
@@ -8,7 +10,130 @@ use std::{error::Error as StdError, fmt::Display};
 pub enum Error {
     Json(serde_json::Error),
     JWT(josekit::JoseError),
-    InvalidStructure,
+    Io(std::io::Error),
+    SignFailed(josekit::JoseError),
+    EncryptFailed(josekit::JoseError),
+    DecryptFailed(josekit::JoseError),
+    VerifyFailed(josekit::JoseError),
+    MissingClaim(&'static str),
+    InvalidClaimType(&'static str),
+    MissingNestedClaim(String),
+    InvalidNestedClaim(String),
+    ReservedClaim(String),
+    ReservedHeader(String),
+    InvalidSessionUrl,
+    NonStringAttribute {
+        key: String,
+    },
+    MalformedToken,
+    WrongTokenType,
+    EncryptionRequired,
+    TokenTooLarge,
+    Expired,
+    TokenTooOld,
+    IssuedInFuture,
+    NotYetValid,
+    AudienceMismatch,
+    IssuerMismatch,
+    NonceMismatch,
+    UnexpectedSubject,
+    AlgorithmMismatch,
+    DisallowedEncryption(String),
+    UnexpectedContentType(String),
+    AuthenticationFailed(AuthStatus),
+    UnsupportedAlgorithm(String),
+    UnsupportedEncryption(String),
+    NoSupportedEncryption,
+    UnknownKey(String),
+    InvalidKey(&'static str),
+    MissingEnv(String),
+    WeakKey {
+        bits: usize,
+        minimum: usize,
+    },
+    KeyTypeMismatch {
+        declared: &'static str,
+        actual: String,
+    },
+    NoMatchingKey(Vec<Error>),
+    Cancelled,
+    Context {
+        message: String,
+        source: Box<dyn StdError + Send + Sync>,
+    },
+}
+
+/// Broad category of an error, for instance to choose an http response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCategory {
+    /// The token is malformed or otherwise unusable (e.g. 400 Bad Request).
+    ClientError,
+    /// The token is not to be trusted, or reports a failed authentication
+    /// (e.g. 401 Unauthorized).
+    AuthFailure,
+    /// The fault lies with the local configuration or environment (e.g. 500
+    /// Internal Server Error).
+    ServerError,
+}
+
+impl Error {
+    /// Determine the broad category of this error.
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            Error::MissingClaim(_)
+            | Error::InvalidClaimType(_)
+            | Error::MissingNestedClaim(_)
+            | Error::InvalidNestedClaim(_)
+            | Error::InvalidSessionUrl
+            | Error::NonStringAttribute { .. }
+            | Error::MalformedToken
+            | Error::WrongTokenType
+            | Error::EncryptionRequired
+            | Error::TokenTooLarge
+            | Error::DecryptFailed(_)
+            | Error::DisallowedEncryption(_)
+            | Error::UnexpectedContentType(_) => ErrorCategory::ClientError,
+            Error::VerifyFailed(_)
+            | Error::Expired
+            | Error::TokenTooOld
+            | Error::IssuedInFuture
+            | Error::NotYetValid
+            | Error::AudienceMismatch
+            | Error::IssuerMismatch
+            | Error::NonceMismatch
+            | Error::UnexpectedSubject
+            | Error::AlgorithmMismatch
+            | Error::AuthenticationFailed(_)
+            | Error::UnknownKey(_)
+            | Error::NoMatchingKey(_) => ErrorCategory::AuthFailure,
+            Error::Json(_)
+            | Error::JWT(_)
+            | Error::Io(_)
+            | Error::SignFailed(_)
+            | Error::EncryptFailed(_)
+            | Error::ReservedClaim(_)
+            | Error::ReservedHeader(_)
+            | Error::UnsupportedAlgorithm(_)
+            | Error::UnsupportedEncryption(_)
+            | Error::NoSupportedEncryption
+            | Error::InvalidKey(_)
+            | Error::MissingEnv(_)
+            | Error::WeakKey { .. }
+            | Error::KeyTypeMismatch { .. }
+            | Error::Cancelled => ErrorCategory::ServerError,
+            Error::Context { source, .. } => source
+                .downcast_ref::<Error>()
+                .map_or(ErrorCategory::ServerError, Error::category),
+        }
+    }
+
+    /// Wrap this error with a message describing where it occurred.
+    pub fn context(self, message: impl Into<String>) -> Error {
+        Error::Context {
+            message: message.into(),
+            source: Box::new(self),
+        }
+    }
 }
 
 impl From<serde_json::Error> for Error {
@@ -23,12 +148,82 @@ impl From<josekit::JoseError> for Error {
     }
 }
 
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Error {
+        Error::Io(e)
+    }
+}
+
 impl Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Error::Json(e) => e.fmt(f),
             Error::JWT(e) => e.fmt(f),
-            Error::InvalidStructure => f.write_str("Incorrect jwe structure"),
+            Error::Io(e) => e.fmt(f),
+            Error::SignFailed(e) => write!(f, "Signing failed: {e}"),
+            Error::EncryptFailed(e) => write!(f, "Encryption failed: {e}"),
+            Error::DecryptFailed(e) => write!(f, "Decryption failed: {e}"),
+            Error::VerifyFailed(e) => write!(f, "Signature verification failed: {e}"),
+            Error::MissingClaim(claim) => write!(f, "Missing claim {claim}"),
+            Error::InvalidClaimType(claim) => write!(f, "Claim {claim} has an invalid type"),
+            Error::MissingNestedClaim(claim) => write!(f, "Missing claim {claim}"),
+            Error::InvalidNestedClaim(claim) => write!(f, "Claim {claim} has an invalid type"),
+            Error::InvalidSessionUrl => f.write_str("Session url is not an absolute http(s) url"),
+            Error::ReservedClaim(claim) => write!(f, "Claim {claim} is reserved"),
+            Error::ReservedHeader(name) => write!(f, "Header parameter {name} is reserved"),
+            Error::NonStringAttribute { key } => {
+                write!(f, "Attribute {key} does not have a string value")
+            }
+            Error::MalformedToken => f.write_str("Malformed token"),
+            Error::WrongTokenType => f.write_str(
+                "Token is an auth result, decode it using decrypt_and_verify_auth_result",
+            ),
+            Error::EncryptionRequired => f.write_str("Token is not encrypted"),
+            Error::TokenTooLarge => f.write_str("Token is too large"),
+            Error::Expired => f.write_str("Token has expired"),
+            Error::TokenTooOld => f.write_str("Token was issued too long ago"),
+            Error::IssuedInFuture => f.write_str("Token was issued in the future"),
+            Error::NotYetValid => f.write_str("Token is not yet valid"),
+            Error::AudienceMismatch => f.write_str("Token is not intended for this audience"),
+            Error::IssuerMismatch => f.write_str("Token was not issued by the expected issuer"),
+            Error::NonceMismatch => f.write_str("Token does not echo the expected nonce"),
+            Error::UnexpectedSubject => f.write_str("Token has an unexpected subject"),
+            Error::AlgorithmMismatch => {
+                f.write_str("Token algorithm does not match that of the verifier")
+            }
+            Error::DisallowedEncryption(enc) => {
+                write!(f, "Content encryption {enc} is not allowed")
+            }
+            Error::UnexpectedContentType(cty) => write!(f, "Unexpected content type {cty}"),
+            Error::AuthenticationFailed(status) => {
+                write!(f, "Authentication failed with status {status:?}")
+            }
+            Error::UnsupportedAlgorithm(alg) => write!(f, "Unsupported algorithm {alg}"),
+            Error::UnsupportedEncryption(enc) => write!(f, "Unsupported content encryption {enc}"),
+            Error::NoSupportedEncryption => {
+                f.write_str("None of the content encryptions is supported")
+            }
+            Error::UnknownKey(kid) => write!(f, "Unknown key id {kid}"),
+            Error::InvalidKey(reason) => write!(f, "Invalid key: {reason}"),
+            Error::MissingEnv(var) => write!(f, "Environment variable {var} is not set"),
+            Error::WeakKey { bits, minimum } => {
+                write!(
+                    f,
+                    "Key of {bits} bits is below the minimum of {minimum} bits"
+                )
+            }
+            Error::KeyTypeMismatch { declared, actual } => {
+                write!(f, "Key of type {actual} configured as {declared}")
+            }
+            Error::NoMatchingKey(errors) => {
+                f.write_str("No matching key")?;
+                for (i, e) in errors.iter().enumerate() {
+                    write!(f, "{} {e}", if i == 0 { ":" } else { ";" })?;
+                }
+                Ok(())
+            }
+            Error::Cancelled => f.write_str("Task was cancelled before completing"),
+            Error::Context { message, .. } => f.write_str(message),
         }
     }
 }
@@ -38,6 +233,12 @@ impl StdError for Error {
         match self {
             Error::Json(e) => Some(e),
             Error::JWT(e) => Some(e),
+            Error::Io(e) => Some(e),
+            Error::SignFailed(e)
+            | Error::EncryptFailed(e)
+            | Error::DecryptFailed(e)
+            | Error::VerifyFailed(e) => Some(e),
+            Error::Context { source, .. } => Some(source.as_ref()),
             _ => None,
         }
     }
@@ -1,52 +1,497 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime},
+};
 
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use josekit::{
-    jwe::{JweDecrypter, JweEncrypter, JweHeader},
+    jwe::{self, JweDecrypter, JweEncrypter, JweHeader, JweHeaderSet},
     jws::{JwsHeader, JwsSigner, JwsVerifier},
-    jwt::{self, JwtPayload, JwtPayloadValidator},
+    jwt::{self, JwtPayload},
 };
-use verder_helpen_proto::{AuthResult, AuthStatus};
+use serde_json::{Map, Value};
+use subtle::ConstantTimeEq;
+use verder_helpen_proto::AuthResult;
 
-use crate::error::Error;
+use crate::{
+    builder::SignEncryptBuilder,
+    claims::{time_claim, VerifiedClaims, RESERVED_CLAIMS},
+    error::Error,
+    observer::{observe, Operation},
+    options::{Serialization, SignOptions, VerificationOptions, DEFAULT_NESTED_CLAIM},
+    status::{legacy_status, parse_status, AuthResultExt, SuccessSpelling},
+    trace::{step_ok, traced},
+};
 
 // Jwe manipulation
 //
 
+/// Subject (`sub`) of the tokens produced by this library.
+const SUBJECT: &str = "verder-helpen-attributes";
+
 /// Sign and encrypt a given set of attributes.
 pub fn sign_and_encrypt_auth_result(
     auth_result: &AuthResult,
     signer: &dyn JwsSigner,
     encrypter: &dyn JweEncrypter,
 ) -> Result<String, Error> {
-    let mut sig_header = JwsHeader::new();
-    sig_header.set_token_type("JWT");
+    SignEncryptBuilder::new().build_and_sign(auth_result, signer, encrypter)
+}
+
+/// Sign and encrypt a given set of attributes, producing the compact
+/// serialized jwe as bytes.
+pub fn sign_and_encrypt_auth_result_bytes(
+    auth_result: &AuthResult,
+    signer: &dyn JwsSigner,
+    encrypter: &dyn JweEncrypter,
+) -> Result<Vec<u8>, Error> {
+    Ok(sign_and_encrypt_auth_result(auth_result, signer, encrypter)?.into_bytes())
+}
+
+/// The five segments of a compact serialized jwe, for instance to store the
+/// protected header separately.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JweParts {
+    /// Base64url encoded protected header.
+    pub protected_header: String,
+    /// Base64url encoded encrypted content key, empty for direct encryption.
+    pub encrypted_key: String,
+    /// Base64url encoded initialization vector.
+    pub iv: String,
+    /// Base64url encoded ciphertext.
+    pub ciphertext: String,
+    /// Base64url encoded authentication tag.
+    pub tag: String,
+}
+
+impl JweParts {
+    /// Split a compact serialized jwe into its segments.
+    pub fn from_compact(jwe: &str) -> Result<Self, Error> {
+        let segments: Vec<&str> = jwe.split('.').collect();
+        match segments[..] {
+            [protected_header, encrypted_key, iv, ciphertext, tag] => Ok(JweParts {
+                protected_header: protected_header.to_string(),
+                encrypted_key: encrypted_key.to_string(),
+                iv: iv.to_string(),
+                ciphertext: ciphertext.to_string(),
+                tag: tag.to_string(),
+            }),
+            _ => Err(Error::MalformedToken),
+        }
+    }
+
+    /// Join the segments into a compact serialized jwe.
+    pub fn to_compact(&self) -> String {
+        [
+            self.protected_header.as_str(),
+            self.encrypted_key.as_str(),
+            self.iv.as_str(),
+            self.ciphertext.as_str(),
+            self.tag.as_str(),
+        ]
+        .join(".")
+    }
+}
+
+/// Sign and encrypt a given set of attributes, producing the segments of the
+/// compact serialized jwe.
+pub fn sign_and_encrypt_auth_result_parts(
+    auth_result: &AuthResult,
+    signer: &dyn JwsSigner,
+    encrypter: &dyn JweEncrypter,
+) -> Result<JweParts, Error> {
+    JweParts::from_compact(&sign_and_encrypt_auth_result(
+        auth_result,
+        signer,
+        encrypter,
+    )?)
+}
+
+/// Sign and encrypt each of the given auth results with the same keys and
+/// default options. Stops at the first result that fails to sign or encrypt,
+/// returning its error.
+pub fn sign_and_encrypt_auth_results(
+    auth_results: &[AuthResult],
+    signer: &dyn JwsSigner,
+    encrypter: &dyn JweEncrypter,
+) -> Result<Vec<String>, Error> {
+    let options = SignOptions::default();
+    auth_results
+        .iter()
+        .map(|auth_result| {
+            sign_and_encrypt_payload(
+                auth_result_to_payload(auth_result)?,
+                signer,
+                encrypter,
+                &options,
+            )
+        })
+        .collect()
+}
+
+/// Sign and encrypt a given set of attributes, producing a token that is valid
+/// for the given lifetime.
+pub fn sign_and_encrypt_auth_result_with_lifetime(
+    auth_result: &AuthResult,
+    signer: &dyn JwsSigner,
+    encrypter: &dyn JweEncrypter,
+    lifetime: Duration,
+) -> Result<String, Error> {
+    sign_and_encrypt_auth_result_with_options(
+        auth_result,
+        signer,
+        encrypter,
+        &SignOptions {
+            lifetime,
+            ..SignOptions::default()
+        },
+    )
+}
+
+/// Sign and encrypt a given set of attributes, using the given signing options.
+pub fn sign_and_encrypt_auth_result_with_options(
+    auth_result: &AuthResult,
+    signer: &dyn JwsSigner,
+    encrypter: &dyn JweEncrypter,
+    options: &SignOptions,
+) -> Result<String, Error> {
+    sign_and_encrypt_payload(
+        auth_result_to_payload(auth_result)?,
+        signer,
+        encrypter,
+        options,
+    )
+}
+
+/// Sign and encrypt a given set of attributes, using the first content
+/// encryption algorithm (`enc`) from the given list of preferences that the
+/// encrypter supports. When encrypting fails for every supported algorithm,
+/// the last of those errors is returned. Fails with
+/// `Error::NoSupportedEncryption` when none of them is supported at all.
+pub fn sign_and_encrypt_auth_result_negotiated(
+    auth_result: &AuthResult,
+    signer: &dyn JwsSigner,
+    encrypter: &dyn JweEncrypter,
+    content_encryptions: &[&str],
+) -> Result<String, Error> {
+    let jws = sign_payload(
+        auth_result_to_payload(auth_result)?,
+        signer,
+        &SignOptions::default(),
+    )?;
+    let mut last_error = Error::NoSupportedEncryption;
+    for content_encryption in content_encryptions {
+        let options = SignOptions {
+            content_encryption: content_encryption.to_string(),
+            ..SignOptions::default()
+        };
+        match encrypt_jws_with_options(jws.clone(), encrypter, &options) {
+            Err(Error::UnsupportedEncryption(_)) => continue,
+            Err(e @ Error::EncryptFailed(_)) => last_error = e,
+            result => return result,
+        }
+    }
+    Err(last_error)
+}
+
+/// Sign a given set of attributes, without encrypting them. The resulting jws
+/// carries the same claims as the one nested in encrypted tokens.
+pub fn sign_auth_result(auth_result: &AuthResult, signer: &dyn JwsSigner) -> Result<String, Error> {
+    sign_payload(
+        auth_result_to_payload(auth_result)?,
+        signer,
+        &SignOptions::default(),
+    )
+}
+
+/// Convert an auth result to the claims carried by the signed payload of a
+/// token. The standard claims, such as `sub` and `exp`, are not included.
+pub fn auth_result_to_payload(auth_result: &AuthResult) -> Result<JwtPayload, Error> {
     let mut sig_payload = JwtPayload::new();
-    sig_payload.set_subject("verder-helpen-attributes");
     sig_payload.set_claim("status", Some(serde_json::to_value(&auth_result.status)?))?;
     if let Some(attributes) = &auth_result.attributes {
         sig_payload.set_claim("attributes", Some(serde_json::to_value(attributes)?))?;
     }
     if let Some(session_url) = &auth_result.session_url {
+        check_session_url(session_url)?;
         sig_payload.set_claim("session_url", Some(serde_json::to_value(session_url)?))?;
     }
-    sig_payload.set_issued_at(&std::time::SystemTime::now());
-    sig_payload
-        .set_expires_at(&(std::time::SystemTime::now() + std::time::Duration::from_secs(5 * 60)));
+    Ok(sig_payload)
+}
 
-    let jws = jwt::encode_with_signer(&sig_payload, &sig_header, signer)?;
+/// Add the standard claims to a payload, then sign and encrypt it.
+pub(crate) fn sign_and_encrypt_payload(
+    sig_payload: JwtPayload,
+    signer: &dyn JwsSigner,
+    encrypter: &dyn JweEncrypter,
+    options: &SignOptions,
+) -> Result<String, Error> {
+    let jws = sign_payload(sig_payload, signer, options)?;
+    encrypt_jws_with_options(jws, encrypter, options)
+}
+
+/// Content encryption algorithms (`enc`) tokens can be produced with.
+const CONTENT_ENCRYPTIONS: &[&str] = &[
+    "A128CBC-HS256",
+    "A192CBC-HS384",
+    "A256CBC-HS512",
+    "A128GCM",
+    "A192GCM",
+    "A256GCM",
+];
 
+/// Registered jwe header parameters (RFC 7516 section 4.1 and RFC 7518
+/// sections 4.6 to 4.8), which cannot be given as extra header parameters, as
+/// they are either set by this library itself or affect how the token is
+/// processed.
+const RESERVED_HEADERS: &[&str] = &[
+    "alg", "enc", "zip", "jku", "jwk", "kid", "x5u", "x5c", "x5t", "x5t#S256", "typ", "cty",
+    "crit", "epk", "apu", "apv", "iv", "tag", "p2s", "p2c",
+];
+
+/// Wrap a signed jws in an encrypted jwe.
+fn encrypt_jws_with_options(
+    jws: String,
+    encrypter: &dyn JweEncrypter,
+    options: &SignOptions,
+) -> Result<String, Error> {
+    if !CONTENT_ENCRYPTIONS.contains(&options.content_encryption.as_str()) {
+        return Err(Error::UnsupportedEncryption(
+            options.content_encryption.clone(),
+        ));
+    }
     let mut enc_header = JweHeader::new();
-    enc_header.set_token_type("JWT");
-    enc_header.set_content_type("JWT");
-    enc_header.set_content_encryption("A128CBC-HS256");
+    enc_header.set_token_type(&options.token_type);
+    enc_header.set_content_type(&options.content_type);
+    enc_header.set_content_encryption(&options.content_encryption);
+    if options.compress && jws.len() > options.compression_threshold {
+        enc_header.set_compression("DEF");
+    }
+    if let Some(kid) = encrypter.key_id() {
+        enc_header.set_key_id(kid);
+    }
+    for (name, value) in &options.extra_headers {
+        if RESERVED_HEADERS.contains(&name.as_str()) {
+            return Err(Error::ReservedHeader(name.clone()));
+        }
+        enc_header.set_claim(name, Some(Value::String(value.clone())))?;
+    }
     let mut enc_payload = JwtPayload::new();
-    enc_payload.set_claim("njwt", Some(serde_json::to_value(jws)?))?;
+    enc_payload.set_claim(&options.nested_claim, Some(serde_json::to_value(jws)?))?;
 
-    Ok(jwt::encode_with_encrypter(
-        &enc_payload,
-        &enc_header,
-        encrypter,
-    )?)
+    match options.serialization {
+        Serialization::Compact => observe(Operation::Encrypt, || {
+            jwt::encode_with_encrypter(&enc_payload, &enc_header, encrypter)
+                .map_err(Error::EncryptFailed)
+        }),
+        Serialization::Json => {
+            let mut header_set = JweHeaderSet::new();
+            for (key, value) in enc_header.claims_set() {
+                header_set.set_claim(key, Some(value.clone()), true)?;
+            }
+            let payload = serde_json::to_vec(enc_payload.claims_set())?;
+            observe(Operation::Encrypt, || {
+                jwe::serialize_flattened_json(&payload, Some(&header_set), None, None, encrypter)
+                    .map_err(Error::EncryptFailed)
+            })
+        }
+    }
+}
+
+/// Add the standard claims to a payload, then sign it.
+fn sign_payload(
+    mut sig_payload: JwtPayload,
+    signer: &dyn JwsSigner,
+    options: &SignOptions,
+) -> Result<String, Error> {
+    let mut sig_header = JwsHeader::new();
+    sig_header.set_token_type("JWT");
+    if let Some(kid) = options.kid.as_deref().or_else(|| signer.key_id()) {
+        sig_header.set_key_id(kid);
+    }
+    for (name, value) in &options.extra_claims {
+        if RESERVED_CLAIMS.contains(&name.as_str()) {
+            return Err(Error::ReservedClaim(name.clone()));
+        }
+        sig_payload.set_claim(name, Some(value.clone()))?;
+    }
+    if options.success_spelling == SuccessSpelling::Legacy {
+        if let Some(status) = sig_payload.claim("status") {
+            let status = legacy_status(status)?;
+            sig_payload.set_claim("status", Some(status))?;
+        }
+    }
+    sig_payload.set_subject(SUBJECT);
+    if let Some(audience) = &options.audience {
+        sig_payload.set_audience(vec![audience.clone()]);
+    }
+    if let Some(issuer) = &options.issuer {
+        sig_payload.set_issuer(issuer);
+    }
+    if let Some(not_before) = &options.not_before {
+        sig_payload.set_not_before(not_before);
+    }
+    let jwt_id = options.jwt_id.clone();
+    #[cfg(feature = "uuid")]
+    let jwt_id = jwt_id.or_else(|| {
+        options
+            .random_jwt_id
+            .then(|| uuid::Uuid::new_v4().to_string())
+    });
+    if let Some(jwt_id) = jwt_id {
+        sig_payload.set_jwt_id(jwt_id);
+    }
+    if let Some(nonce) = &options.nonce {
+        sig_payload.set_claim("nonce", Some(Value::String(nonce.clone())))?;
+    }
+    if !options.attribute_status.is_empty() {
+        let attribute_status = serde_json::to_value(&options.attribute_status)?;
+        sig_payload.set_claim("attribute_status", Some(attribute_status))?;
+    }
+    let now = options.issued_at.unwrap_or_else(SystemTime::now);
+    sig_payload.set_issued_at(&now);
+    sig_payload.set_expires_at(&(now + options.lifetime));
+
+    observe(Operation::Sign, || {
+        jwt::encode_with_signer(&sig_payload, &sig_header, signer).map_err(Error::SignFailed)
+    })
+}
+
+/// Decrypt a given jwe and return the nested jws exactly as it was signed, for
+/// instance to store it and verify it later with `verify_auth_result`. Its
+/// signature and claims are not verified.
+pub fn extract_inner_jws(jwe: &str, decrypter: &dyn JweDecrypter) -> Result<String, Error> {
+    check_token_size(jwe, &VerificationOptions::default())?;
+    decrypt_nested_jws(jwe, decrypter)
+}
+
+/// Wrap a jws signed elsewhere, for instance in an HSM, in the same encrypted
+/// envelope as used by `sign_and_encrypt_auth_result`. The jws itself is not
+/// checked.
+pub fn encrypt_jws(jws: &str, encrypter: &dyn JweEncrypter) -> Result<String, Error> {
+    encrypt_jws_with_options(jws.to_string(), encrypter, &SignOptions::default())
+}
+
+/// Decrypt a given jwe and encrypt the nested jws again for a different
+/// recipient. The nested jws, including its signature, is left untouched, so
+/// no signer is needed. Its signature and claims are not verified either.
+pub fn reencrypt_auth_result(
+    jwe: &str,
+    old_decrypter: &dyn JweDecrypter,
+    new_encrypter: &dyn JweEncrypter,
+) -> Result<String, Error> {
+    check_token_size(jwe, &VerificationOptions::default())?;
+    let jws = decrypt_nested_jws(jwe, old_decrypter)?;
+    encrypt_jws_with_options(jws, new_encrypter, &SignOptions::default())
+}
+
+/// Maximum amount of time an `iat` claim may lie in the future, to allow for
+/// small clock differences between issuer and verifier.
+const ISSUED_AT_TOLERANCE: Duration = Duration::from_secs(30);
+
+fn validate_times(
+    payload: &JwtPayload,
+    now: SystemTime,
+    options: &VerificationOptions,
+) -> Result<(), Error> {
+    // Claims or options large enough to overflow when added up cannot be
+    // exceeded, so those checks pass.
+    let expires_at = time_claim(payload, "exp")?.ok_or(Error::MissingClaim("exp"))?;
+    if matches!(expires_at.checked_add(options.leeway), Some(t) if t <= now) {
+        return Err(Error::Expired);
+    }
+    let issued_at = time_claim(payload, "iat")?;
+    if let Some(not_before) = time_claim(payload, "nbf")? {
+        if matches!(now.checked_add(options.leeway), Some(t) if not_before > t) {
+            return Err(Error::NotYetValid);
+        }
+    }
+    if let Some(issued_at) = issued_at {
+        let latest = now
+            .checked_add(ISSUED_AT_TOLERANCE)
+            .and_then(|t| t.checked_add(options.leeway));
+        if matches!(latest, Some(t) if issued_at > t) {
+            return Err(Error::IssuedInFuture);
+        }
+    }
+    if let Some(max_age) = options.max_age {
+        let issued_at = issued_at.ok_or(Error::MissingClaim("iat"))?;
+        let max_age = max_age.checked_add(options.leeway);
+        if matches!(max_age.and_then(|age| issued_at.checked_add(age)), Some(t) if t < now) {
+            return Err(Error::TokenTooOld);
+        }
+    }
+    step_ok("exp");
+    Ok(())
+}
+
+fn validate_claims(payload: &JwtPayload, options: &VerificationOptions) -> Result<(), Error> {
+    if !options.skip_subject_check && payload.subject() != Some(SUBJECT) {
+        return Err(Error::UnexpectedSubject);
+    }
+    if let Some(expected_audience) = &options.expected_audience {
+        let audience = payload.audience().unwrap_or_default();
+        if !audience.contains(&expected_audience.as_str()) {
+            return Err(Error::AudienceMismatch);
+        }
+        step_ok("aud");
+    }
+    if let Some(expected_issuer) = &options.expected_issuer {
+        if payload.issuer() != Some(expected_issuer.as_str()) {
+            return Err(Error::IssuerMismatch);
+        }
+        step_ok("iss");
+    }
+    if let Some(expected_nonce) = &options.expected_nonce {
+        if payload.claim("nonce").and_then(Value::as_str) != Some(expected_nonce.as_str()) {
+            return Err(Error::NonceMismatch);
+        }
+        step_ok("nonce");
+    }
+    Ok(())
+}
+
+pub(crate) fn decrypt_nested_jws(jwe: &str, decrypter: &dyn JweDecrypter) -> Result<String, Error> {
+    decrypt_nested_claim(jwe, decrypter, DEFAULT_NESTED_CLAIM)
+}
+
+// Decrypt a jwe, taking the nested jws from the claim of the given name.
+fn decrypt_nested_claim(
+    jwe: &str,
+    decrypter: &dyn JweDecrypter,
+    nested_claim: &str,
+) -> Result<String, Error> {
+    let decoded_jwe = decrypt_outer_payload(jwe, decrypter)?;
+    let jws = nested_jws(&decoded_jwe, nested_claim)?;
+    step_ok("decrypt");
+    Ok(jws.to_string())
+}
+
+// Take the nested jws from the claim of the given name of a decrypted payload.
+fn nested_jws<'a>(payload: &'a JwtPayload, nested_claim: &str) -> Result<&'a str, Error> {
+    payload
+        .claim(nested_claim)
+        .ok_or_else(|| Error::MissingNestedClaim(nested_claim.to_string()))?
+        .as_str()
+        .ok_or_else(|| Error::InvalidNestedClaim(nested_claim.to_string()))
+}
+
+// Decrypt a jwe in either serialization, returning its payload.
+fn decrypt_outer_payload(jwe: &str, decrypter: &dyn JweDecrypter) -> Result<JwtPayload, Error> {
+    if is_json_serialized(jwe) {
+        let payload = observe(Operation::Decrypt, || {
+            jwe::deserialize_json(jwe, decrypter).map_err(Error::DecryptFailed)
+        })?
+        .0;
+        let claims = serde_json::from_slice::<Map<String, Value>>(&payload)
+            .map_err(|_| Error::MalformedToken)?;
+        Ok(JwtPayload::from_map(claims)?)
+    } else {
+        Ok(observe(Operation::Decrypt, || {
+            jwt::decode_with_decrypter(jwe, decrypter).map_err(Error::DecryptFailed)
+        })?
+        .0)
+    }
 }
 
 fn raw_decrypt_and_verify_auth_result(
@@ -54,33 +499,131 @@ fn raw_decrypt_and_verify_auth_result(
     validator: &dyn JwsVerifier,
     decrypter: &dyn JweDecrypter,
     do_time_validation: bool,
+    options: &VerificationOptions,
 ) -> Result<AuthResult, Error> {
-    let decoded_jwe = jwt::decode_with_decrypter(jwe, decrypter)?.0;
-    let jws = decoded_jwe
-        .claim("njwt")
-        .ok_or(Error::InvalidStructure)?
-        .as_str()
-        .ok_or(Error::InvalidStructure)?;
-    let decoded_jws = jwt::decode_with_verifier(jws, validator)?.0;
-    if do_time_validation {
-        let mut validator = JwtPayloadValidator::new();
-        validator.set_base_time(std::time::SystemTime::now());
-        validator.validate(&decoded_jws)?;
-    }
-    let status = decoded_jws.claim("status").ok_or(Error::InvalidStructure)?;
-    let status = serde_json::from_value::<AuthStatus>(status.clone())?;
+    traced("decrypt_and_verify_auth_result", || {
+        let jws = decrypt_checked(jwe, decrypter, options)?;
+        verify_nested_jws(&jws, validator, do_time_validation, options)
+    })
+}
+
+// Check the size and header of a jwe against the options, then decrypt it and
+// return the nested jws.
+pub(crate) fn decrypt_checked(
+    jwe: &str,
+    decrypter: &dyn JweDecrypter,
+    options: &VerificationOptions,
+) -> Result<String, Error> {
+    check_token_size(jwe, options)?;
+    check_content_encryption(jwe, options)?;
+    check_content_type(jwe, options)?;
+    decrypt_nested_claim(jwe, decrypter, &options.nested_claim)
+}
+
+pub(crate) fn check_token_size(jwe: &str, options: &VerificationOptions) -> Result<(), Error> {
+    if jwe.len() > options.max_token_size {
+        return Err(Error::TokenTooLarge);
+    }
+    Ok(())
+}
+
+fn check_content_encryption(jwe: &str, options: &VerificationOptions) -> Result<(), Error> {
+    if let Some(allowed) = &options.allowed_content_encryption {
+        let enc = peek_jwe_header(jwe)?
+            .content_encryption()
+            .ok_or(Error::MissingClaim("enc"))?
+            .to_string();
+        if !allowed.contains(&enc) {
+            return Err(Error::DisallowedEncryption(enc));
+        }
+    }
+    Ok(())
+}
+
+fn check_content_type(jwe: &str, options: &VerificationOptions) -> Result<(), Error> {
+    if let Some(expected) = &options.expected_content_type {
+        let cty = peek_jwe_header(jwe)?
+            .content_type()
+            .ok_or(Error::MissingClaim("cty"))?
+            .to_string();
+        if &cty != expected {
+            return Err(Error::UnexpectedContentType(cty));
+        }
+    }
+    Ok(())
+}
+
+/// Signature algorithms (`alg`) known to josekit, including the unsigned
+/// `none`.
+const SIGNATURE_ALGORITHMS: &[&str] = &[
+    "HS256", "HS384", "HS512", "RS256", "RS384", "RS512", "PS256", "PS384", "PS512", "ES256",
+    "ES384", "ES512", "ES256K", "EdDSA", "none",
+];
+
+/// Verify a nested jws and check its standard claims.
+pub(crate) fn verify_nested_payload(
+    jws: &str,
+    validator: &dyn JwsVerifier,
+    do_time_validation: bool,
+    options: &VerificationOptions,
+) -> Result<(JwtPayload, JwsHeader), Error> {
+    observe(Operation::Verify, || {
+        // Reject tokens claiming a different algorithm than that of the
+        // verifier before handing them to it, guarding against algorithm
+        // confusion. Unknown algorithms are named, to ease debugging.
+        let header = peek_jws_header(jws)?;
+        match header.algorithm() {
+            Some(alg) if !SIGNATURE_ALGORITHMS.contains(&alg) => {
+                return Err(Error::UnsupportedAlgorithm(alg.to_string()))
+            }
+            Some(alg) if alg == validator.algorithm().name() => {}
+            _ => return Err(Error::AlgorithmMismatch),
+        }
+        let (decoded_jws, header) =
+            jwt::decode_with_verifier(jws, validator).map_err(Error::VerifyFailed)?;
+        step_ok("signature");
+        if do_time_validation {
+            let now = options.now.unwrap_or_else(SystemTime::now);
+            validate_times(&decoded_jws, now, options)?;
+        }
+        validate_claims(&decoded_jws, options)?;
+        Ok((decoded_jws, header))
+    })
+}
+
+pub(crate) fn verify_nested_jws(
+    jws: &str,
+    validator: &dyn JwsVerifier,
+    do_time_validation: bool,
+    options: &VerificationOptions,
+) -> Result<AuthResult, Error> {
+    let (decoded_jws, _) = verify_nested_payload(jws, validator, do_time_validation, options)?;
+    payload_to_auth_result(&decoded_jws)
+}
+
+/// Convert the claims of a signed payload back to an auth result. The
+/// standard claims are ignored, so these should be checked separately.
+pub fn payload_to_auth_result(decoded_jws: &JwtPayload) -> Result<AuthResult, Error> {
+    let status = decoded_jws
+        .claim("status")
+        .ok_or(Error::MissingClaim("status"))?;
+    let status = parse_status(status)?;
     let attributes = decoded_jws.claim("attributes");
     let attributes = match attributes {
-        Some(raw_attributes) => Some(serde_json::from_value::<HashMap<String, String>>(
-            raw_attributes.clone(),
-        )?),
+        Some(raw_attributes) => Some(string_attributes(raw_attributes)?),
         None => None,
     };
     let session_url = decoded_jws.claim("session_url");
     let session_url = match session_url {
-        Some(session_url) => Some(serde_json::from_value::<String>(session_url.clone())?),
+        Some(session_url) => Some(
+            serde_json::from_value::<String>(session_url.clone())
+                .map_err(|_| Error::InvalidClaimType("session_url"))?,
+        ),
         None => None,
     };
+    if let Some(session_url) = &session_url {
+        check_session_url(session_url)?;
+    }
 
     Ok(AuthResult {
         status,
@@ -89,19 +632,547 @@ fn raw_decrypt_and_verify_auth_result(
     })
 }
 
+// With the url feature, require session urls to be absolute http(s) urls.
+#[cfg(feature = "url")]
+fn check_session_url(session_url: &str) -> Result<(), Error> {
+    match url::Url::parse(session_url) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => Ok(()),
+        _ => Err(Error::InvalidSessionUrl),
+    }
+}
+
+#[cfg(not(feature = "url"))]
+fn check_session_url(_session_url: &str) -> Result<(), Error> {
+    Ok(())
+}
+
+// Convert an attributes claim to a map of strings, naming the first attribute
+// whose value is not a string.
+fn string_attributes(raw_attributes: &Value) -> Result<HashMap<String, String>, Error> {
+    raw_attributes
+        .as_object()
+        .ok_or(Error::InvalidClaimType("attributes"))?
+        .iter()
+        .map(|(key, value)| match value {
+            Value::String(value) => Ok((key.clone(), value.clone())),
+            _ => Err(Error::NonStringAttribute { key: key.clone() }),
+        })
+        .collect()
+}
+
 pub fn dangerous_decrypt_auth_result_without_verifying_expiration(
     jwe: &str,
     validator: &dyn JwsVerifier,
     decrypter: &dyn JweDecrypter,
 ) -> Result<AuthResult, Error> {
-    raw_decrypt_and_verify_auth_result(jwe, validator, decrypter, false)
+    raw_decrypt_and_verify_auth_result(
+        jwe,
+        validator,
+        decrypter,
+        false,
+        &VerificationOptions::default(),
+    )
+}
+
+/// Validity in time of a token whose signature has been verified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Validity {
+    /// The token is currently valid.
+    Valid,
+    /// The token has expired, or was issued too long ago.
+    Expired,
+    /// The token is not valid yet, or was issued in the future.
+    NotYetValid,
+}
+
+/// Decrypt a given jwe and verify its signature, reporting rather than
+/// rejecting expired and not yet valid tokens, for instance to inspect old
+/// tokens. The returned validity must be checked before trusting the result;
+/// production code should use `decrypt_and_verify_auth_result` instead.
+pub fn dangerous_decrypt_auth_result_with_validity(
+    jwe: &str,
+    validator: &dyn JwsVerifier,
+    decrypter: &dyn JweDecrypter,
+) -> Result<(AuthResult, Validity), Error> {
+    dangerous_decrypt_auth_result_with_validity_and_options(
+        jwe,
+        validator,
+        decrypter,
+        &VerificationOptions::default(),
+    )
+}
+
+/// Decrypt a given jwe and verify its signature like
+/// `dangerous_decrypt_auth_result_with_validity`, using the given verification
+/// options. The time claims are reported against these options rather than
+/// rejected; all other checks apply as usual.
+pub fn dangerous_decrypt_auth_result_with_validity_and_options(
+    jwe: &str,
+    validator: &dyn JwsVerifier,
+    decrypter: &dyn JweDecrypter,
+    options: &VerificationOptions,
+) -> Result<(AuthResult, Validity), Error> {
+    let jws = decrypt_checked(jwe, decrypter, options)?;
+    let (decoded_jws, _) = verify_nested_payload(&jws, validator, false, options)?;
+    let now = options.now.unwrap_or_else(SystemTime::now);
+    let validity = match validate_times(&decoded_jws, now, options) {
+        Ok(()) => Validity::Valid,
+        Err(Error::Expired | Error::TokenTooOld) => Validity::Expired,
+        Err(Error::NotYetValid | Error::IssuedInFuture) => Validity::NotYetValid,
+        Err(e) => return Err(e),
+    };
+    Ok((payload_to_auth_result(&decoded_jws)?, validity))
 }
 
-/// Decrypt and verify a given jwe to extract the contained attributes.
+/// Decrypt and verify a given jwe to extract the contained attributes. Like all
+/// decoding functions of this library, it returns an error rather than
+/// panicking on malformed or otherwise untrusted input.
 pub fn decrypt_and_verify_auth_result(
     jwe: &str,
     validator: &dyn JwsVerifier,
     decrypter: &dyn JweDecrypter,
 ) -> Result<AuthResult, Error> {
-    raw_decrypt_and_verify_auth_result(jwe, validator, decrypter, true)
+    decrypt_and_verify_auth_result_with_options(
+        jwe,
+        validator,
+        decrypter,
+        &VerificationOptions::default(),
+    )
+}
+
+/// Decrypt and verify a given jwe to extract the contained attributes,
+/// explicitly rejecting tokens that are only signed with
+/// `Error::EncryptionRequired`.
+pub fn decrypt_and_verify_auth_result_strict(
+    jwe: &str,
+    validator: &dyn JwsVerifier,
+    decrypter: &dyn JweDecrypter,
+) -> Result<AuthResult, Error> {
+    decrypt_and_verify_auth_result_strict_with_options(
+        jwe,
+        validator,
+        decrypter,
+        &VerificationOptions::default(),
+    )
+}
+
+/// Decrypt and verify a given jwe like `decrypt_and_verify_auth_result_strict`,
+/// using the given verification options.
+pub fn decrypt_and_verify_auth_result_strict_with_options(
+    jwe: &str,
+    validator: &dyn JwsVerifier,
+    decrypter: &dyn JweDecrypter,
+    options: &VerificationOptions,
+) -> Result<AuthResult, Error> {
+    check_token_size(jwe, options)?;
+    if token_kind(jwe)? == TokenKind::Jws {
+        return Err(Error::EncryptionRequired);
+    }
+    decrypt_and_verify_auth_result_with_options(jwe, validator, decrypter, options)
+}
+
+/// Verify a given jws produced by `sign_auth_result` to extract the contained
+/// attributes.
+pub fn verify_auth_result(jws: &str, validator: &dyn JwsVerifier) -> Result<AuthResult, Error> {
+    verify_auth_result_with_options(jws, validator, &VerificationOptions::default())
+}
+
+/// Verify a given jws produced by `sign_auth_result` to extract the contained
+/// attributes, using the given verification options. The claims are checked
+/// exactly as for the jws nested in an encrypted token.
+pub fn verify_auth_result_with_options(
+    jws: &str,
+    validator: &dyn JwsVerifier,
+    options: &VerificationOptions,
+) -> Result<AuthResult, Error> {
+    check_token_size(jws, options)?;
+    verify_nested_jws(jws, validator, true, options)
+}
+
+/// Decrypt and verify a given jwe, given as bytes, to extract the contained
+/// attributes.
+pub fn decrypt_and_verify_auth_result_bytes(
+    jwe: &[u8],
+    validator: &dyn JwsVerifier,
+    decrypter: &dyn JweDecrypter,
+) -> Result<AuthResult, Error> {
+    let jwe = std::str::from_utf8(jwe).map_err(|_| Error::MalformedToken)?;
+    decrypt_and_verify_auth_result(jwe, validator, decrypter)
+}
+
+/// Decrypt and verify a given jwe to extract the contained attributes, using
+/// the given verification options.
+pub fn decrypt_and_verify_auth_result_with_options(
+    jwe: &str,
+    validator: &dyn JwsVerifier,
+    decrypter: &dyn JweDecrypter,
+    options: &VerificationOptions,
+) -> Result<AuthResult, Error> {
+    raw_decrypt_and_verify_auth_result(jwe, validator, decrypter, true, options)
+}
+
+/// Decrypt and verify a given jwe to extract the contained attributes, along
+/// with the standard claims of the nested jws.
+pub fn decrypt_and_verify_auth_result_detailed(
+    jwe: &str,
+    validator: &dyn JwsVerifier,
+    decrypter: &dyn JweDecrypter,
+) -> Result<(AuthResult, VerifiedClaims), Error> {
+    decrypt_and_verify_auth_result_detailed_with_options(
+        jwe,
+        validator,
+        decrypter,
+        &VerificationOptions::default(),
+    )
+}
+
+/// Decrypt and verify a given jwe to extract the contained attributes, along
+/// with the standard claims of the nested jws, using the given verification
+/// options.
+pub fn decrypt_and_verify_auth_result_detailed_with_options(
+    jwe: &str,
+    validator: &dyn JwsVerifier,
+    decrypter: &dyn JweDecrypter,
+    options: &VerificationOptions,
+) -> Result<(AuthResult, VerifiedClaims), Error> {
+    let jws = decrypt_checked(jwe, decrypter, options)?;
+    let (decoded_jws, header) = verify_nested_payload(&jws, validator, true, options)?;
+    let auth_result = payload_to_auth_result(&decoded_jws)?;
+    let claims = VerifiedClaims::from_parts(&decoded_jws, &header)?;
+    Ok((auth_result, claims))
+}
+
+/// Decrypt and verify a given jwe, returning only the sorted names of the
+/// contained attributes, for instance to record which attributes were disclosed
+/// without handling their values.
+pub fn decrypt_and_list_attribute_keys(
+    jwe: &str,
+    validator: &dyn JwsVerifier,
+    decrypter: &dyn JweDecrypter,
+) -> Result<Vec<String>, Error> {
+    let auth_result = decrypt_and_verify_auth_result(jwe, validator, decrypter)?;
+    let mut keys: Vec<String> = auth_result
+        .attributes
+        .map(|attributes| attributes.into_keys().collect())
+        .unwrap_or_default();
+    keys.sort();
+    Ok(keys)
+}
+
+/// Check that an auth result represents a successful authentication, turning
+/// any other status into `Error::AuthenticationFailed` carrying that status.
+pub fn require_success(auth_result: AuthResult) -> Result<AuthResult, Error> {
+    if auth_result.is_success() {
+        Ok(auth_result)
+    } else {
+        Err(Error::AuthenticationFailed(auth_result.status))
+    }
+}
+
+// Header inspection
+//
+
+fn raw_segment(token: &str, segments: usize, index: usize) -> Result<Vec<u8>, Error> {
+    let parts: Vec<&str> = token.split('.').collect();
+    if parts.len() != segments {
+        return Err(Error::MalformedToken);
+    }
+    URL_SAFE_NO_PAD
+        .decode(parts[index])
+        .map_err(|_| Error::MalformedToken)
+}
+
+// Whether a jwe uses the json rather than the compact serialization.
+fn is_json_serialized(jwe: &str) -> bool {
+    jwe.trim_start().starts_with('{')
+}
+
+/// Read the protected header of a compact or json serialized jwe without
+/// decrypting it, for instance to select a decrypter based on the `kid`.
+pub fn peek_jwe_header(jwe: &str) -> Result<JweHeader, Error> {
+    if is_json_serialized(jwe) {
+        let jwe =
+            serde_json::from_str::<Map<String, Value>>(jwe).map_err(|_| Error::MalformedToken)?;
+        let protected = jwe
+            .get("protected")
+            .and_then(Value::as_str)
+            .ok_or(Error::MalformedToken)?;
+        let protected = URL_SAFE_NO_PAD
+            .decode(protected)
+            .map_err(|_| Error::MalformedToken)?;
+        return Ok(JweHeader::from_bytes(&protected)?);
+    }
+    Ok(JweHeader::from_bytes(&raw_segment(jwe, 5, 0)?)?)
+}
+
+/// Read the header of a compact serialized jws without verifying it, for
+/// instance to select a verifier based on the `kid`.
+pub fn peek_jws_header(jws: &str) -> Result<JwsHeader, Error> {
+    Ok(JwsHeader::from_bytes(&raw_segment(jws, 3, 0)?)?)
+}
+
+// Token inspection
+//
+
+/// Kind of a serialized token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// A signed-only token, as produced by `sign_auth_result`.
+    Jws,
+    /// An encrypted token, in either compact or json serialization.
+    Jwe,
+}
+
+/// Determine whether a token is a jws or a jwe, based on its structure only.
+pub fn token_kind(token: &str) -> Result<TokenKind, Error> {
+    if is_json_serialized(token) {
+        return Ok(TokenKind::Jwe);
+    }
+    match token.split('.').count() {
+        3 => Ok(TokenKind::Jws),
+        5 => Ok(TokenKind::Jwe),
+        _ => Err(Error::MalformedToken),
+    }
+}
+
+/// Structure of a token, for diagnostics. Only the names of the claims are
+/// included, not their values.
+#[derive(Debug, Clone)]
+pub struct TokenDescription {
+    /// Protected header of the outer jwe.
+    pub outer_header: JweHeader,
+    /// Sorted names of the claims of the outer jwe.
+    pub outer_claims: Vec<String>,
+    /// Header of the nested jws.
+    pub inner_header: JwsHeader,
+    /// Sorted names of the claims of the nested jws.
+    pub inner_claims: Vec<String>,
+}
+
+/// Describe the structure of a token, for instance to diagnose integration
+/// problems. The signature of the nested jws is verified, but its claims are
+/// not checked, so the description says nothing about the validity of the
+/// token.
+pub fn describe_token(
+    token: &str,
+    decrypter: &dyn JweDecrypter,
+    verifier: &dyn JwsVerifier,
+) -> Result<TokenDescription, Error> {
+    let claim_names = |payload: &JwtPayload| {
+        let mut names: Vec<String> = payload.claims_set().keys().cloned().collect();
+        names.sort();
+        names
+    };
+    check_token_size(token, &VerificationOptions::default())?;
+    let outer_header = peek_jwe_header(token)?;
+    let outer_payload = decrypt_outer_payload(token, decrypter)?;
+    let jws = nested_jws(&outer_payload, DEFAULT_NESTED_CLAIM)?;
+    let (inner_payload, inner_header) =
+        jwt::decode_with_verifier(jws, verifier).map_err(Error::VerifyFailed)?;
+    Ok(TokenDescription {
+        outer_header,
+        outer_claims: claim_names(&outer_payload),
+        inner_header,
+        inner_claims: claim_names(&inner_payload),
+    })
+}
+
+// Read the payload of a compact serialized jws without verifying it.
+pub(crate) fn peek_jws_payload(jws: &str) -> Result<JwtPayload, Error> {
+    let claims = serde_json::from_slice::<Map<String, Value>>(&raw_segment(jws, 3, 1)?)
+        .map_err(|_| Error::MalformedToken)?;
+    Ok(JwtPayload::from_map(claims)?)
+}
+
+/// Compare two tokens, or other values such as token ids, in constant time,
+/// so that the comparison does not leak how much of them matches. Only their
+/// lengths may be inferred from timing.
+pub fn tokens_eq(a: &str, b: &str) -> bool {
+    a.as_bytes().ct_eq(b.as_bytes()).into()
+}
+
+/// Determine how long a token remains valid, based on the `exp` claim of the
+/// nested jws. Tokens that have already expired yield a zero duration rather
+/// than an error.
+///
+/// The signature of the nested jws is *not* verified, so the result is only
+/// suitable for purposes such as picking a cache ttl, not for deciding whether
+/// to trust the token.
+pub fn token_remaining_validity(
+    jwe: &str,
+    decrypter: &dyn JweDecrypter,
+) -> Result<Duration, Error> {
+    check_token_size(jwe, &VerificationOptions::default())?;
+    let jws = decrypt_nested_jws(jwe, decrypter)?;
+    let expires_at =
+        time_claim(&peek_jws_payload(&jws)?, "exp")?.ok_or(Error::MissingClaim("exp"))?;
+    Ok(expires_at
+        .duration_since(SystemTime::now())
+        .unwrap_or(Duration::ZERO))
+}
+
+// Tests
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload_expiring_at(expires_at: SystemTime) -> JwtPayload {
+        let mut payload = JwtPayload::new();
+        payload.set_issued_at(&(expires_at - Duration::from_secs(5 * 60)));
+        payload.set_expires_at(&expires_at);
+        payload
+    }
+
+    #[test]
+    fn test_expiry_leeway_boundaries() {
+        // JWT timestamps have a resolution of whole seconds
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let options = VerificationOptions {
+            leeway: Duration::from_secs(60),
+            ..VerificationOptions::default()
+        };
+
+        // Expired, but less than the leeway ago
+        let payload = payload_expiring_at(now - Duration::from_secs(59));
+        assert!(validate_times(&payload, now, &options).is_ok());
+
+        // Expired exactly the leeway ago
+        let payload = payload_expiring_at(now - Duration::from_secs(60));
+        assert!(matches!(
+            validate_times(&payload, now, &options),
+            Err(Error::Expired)
+        ));
+
+        // Expired more than the leeway ago
+        let payload = payload_expiring_at(now - Duration::from_secs(61));
+        assert!(matches!(
+            validate_times(&payload, now, &options),
+            Err(Error::Expired)
+        ));
+
+        // Without leeway, any expiry in the past is rejected
+        let payload = payload_expiring_at(now - Duration::from_secs(1));
+        assert!(matches!(
+            validate_times(&payload, now, &VerificationOptions::default()),
+            Err(Error::Expired)
+        ));
+    }
+
+    #[test]
+    fn test_not_before_leeway_boundaries() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let options = VerificationOptions {
+            leeway: Duration::from_secs(60),
+            ..VerificationOptions::default()
+        };
+        let payload_not_before = |not_before: SystemTime| {
+            let mut payload = payload_expiring_at(now + Duration::from_secs(5 * 60));
+            payload.set_not_before(&not_before);
+            payload
+        };
+
+        let payload = payload_not_before(now + Duration::from_secs(60));
+        assert!(validate_times(&payload, now, &options).is_ok());
+
+        let payload = payload_not_before(now + Duration::from_secs(61));
+        assert!(matches!(
+            validate_times(&payload, now, &options),
+            Err(Error::NotYetValid)
+        ));
+
+        let payload = payload_not_before(now);
+        assert!(validate_times(&payload, now, &VerificationOptions::default()).is_ok());
+    }
+
+    #[test]
+    fn test_issued_at_leeway_boundaries() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let options = VerificationOptions {
+            leeway: Duration::from_secs(60),
+            ..VerificationOptions::default()
+        };
+        let payload_issued_at = |issued_at: SystemTime| {
+            let mut payload = JwtPayload::new();
+            payload.set_issued_at(&issued_at);
+            payload.set_expires_at(&(issued_at + Duration::from_secs(5 * 60)));
+            payload
+        };
+
+        let payload = payload_issued_at(now + ISSUED_AT_TOLERANCE + Duration::from_secs(59));
+        assert!(validate_times(&payload, now, &options).is_ok());
+
+        let payload = payload_issued_at(now + ISSUED_AT_TOLERANCE + Duration::from_secs(60));
+        assert!(validate_times(&payload, now, &options).is_ok());
+
+        let payload = payload_issued_at(now + ISSUED_AT_TOLERANCE + Duration::from_secs(61));
+        assert!(matches!(
+            validate_times(&payload, now, &options),
+            Err(Error::IssuedInFuture)
+        ));
+    }
+
+    #[test]
+    fn test_max_age_boundaries() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let options = VerificationOptions {
+            max_age: Some(Duration::from_secs(15 * 60)),
+            ..VerificationOptions::default()
+        };
+        // Far from expiring, so that only the age is of concern
+        let payload_issued_at = |issued_at: SystemTime| {
+            let mut payload = JwtPayload::new();
+            payload.set_issued_at(&issued_at);
+            payload.set_expires_at(&(now + Duration::from_secs(24 * 60 * 60)));
+            payload
+        };
+
+        let payload = payload_issued_at(now - Duration::from_secs(15 * 60 - 1));
+        assert!(validate_times(&payload, now, &options).is_ok());
+
+        let payload = payload_issued_at(now - Duration::from_secs(15 * 60));
+        assert!(validate_times(&payload, now, &options).is_ok());
+
+        let payload = payload_issued_at(now - Duration::from_secs(15 * 60 + 1));
+        assert!(matches!(
+            validate_times(&payload, now, &options),
+            Err(Error::TokenTooOld)
+        ));
+
+        let mut payload = JwtPayload::new();
+        payload.set_expires_at(&(now + Duration::from_secs(60)));
+        assert!(matches!(
+            validate_times(&payload, now, &options),
+            Err(Error::MissingClaim("iat"))
+        ));
+        assert!(validate_times(&payload, now, &VerificationOptions::default()).is_ok());
+    }
+
+    #[test]
+    fn test_unbounded_options() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let mut payload = payload_expiring_at(now - Duration::from_secs(60 * 60));
+        payload.set_not_before(&(now + Duration::from_secs(60 * 60)));
+        payload.set_issued_at(&(now + Duration::from_secs(60 * 60)));
+
+        let options = VerificationOptions {
+            leeway: Duration::MAX,
+            ..VerificationOptions::default()
+        };
+        assert!(validate_times(&payload, now, &options).is_ok());
+
+        let payload = payload_expiring_at(now + Duration::from_secs(60));
+        let options = VerificationOptions {
+            max_age: Some(Duration::MAX),
+            ..VerificationOptions::default()
+        };
+        assert!(validate_times(&payload, now, &options).is_ok());
+        let options = VerificationOptions {
+            max_age: Some(Duration::MAX),
+            leeway: Duration::MAX,
+            ..VerificationOptions::default()
+        };
+        assert!(validate_times(&payload, now, &options).is_ok());
+    }
 }
@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+
+use josekit::{jwe::JweDecrypter, jws::JwsVerifier, JoseError};
+use verder_helpen_proto::AuthResult;
+
+use crate::{
+    error::Error,
+    jwt::{
+        check_token_size, decrypt_checked, decrypt_nested_jws, peek_jwe_header, peek_jws_header,
+        peek_jws_payload, verify_nested_jws,
+    },
+    options::VerificationOptions,
+};
+
+// Key selection
+//
+
+/// Set of decrypters and verifiers indexed by key id, allowing tokens made
+/// with several generations of keys to be accepted during key rotation.
+#[derive(Default)]
+pub struct KeySet {
+    decrypters: HashMap<String, Box<dyn JweDecrypter>>,
+    verifiers: HashMap<String, Box<dyn JwsVerifier>>,
+}
+
+impl KeySet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a decrypter, used for jwes with the given key id.
+    pub fn add_decrypter(&mut self, kid: impl Into<String>, decrypter: Box<dyn JweDecrypter>) {
+        self.decrypters.insert(kid.into(), decrypter);
+    }
+
+    /// Add a verifier, used for jwss with the given key id.
+    pub fn add_verifier(&mut self, kid: impl Into<String>, verifier: Box<dyn JwsVerifier>) {
+        self.verifiers.insert(kid.into(), verifier);
+    }
+
+    fn decrypter(&self, kid: Option<&str>) -> Result<&dyn JweDecrypter, Error> {
+        let kid = kid.ok_or(Error::MissingClaim("kid"))?;
+        self.decrypters
+            .get(kid)
+            .map(|decrypter| decrypter.as_ref())
+            .ok_or_else(|| Error::UnknownKey(kid.to_string()))
+    }
+
+    fn verifier(&self, kid: Option<&str>) -> Result<&dyn JwsVerifier, Error> {
+        let kid = kid.ok_or(Error::MissingClaim("kid"))?;
+        self.verifiers
+            .get(kid)
+            .map(|verifier| verifier.as_ref())
+            .ok_or_else(|| Error::UnknownKey(kid.to_string()))
+    }
+}
+
+/// Decrypt and verify a given jwe to extract the contained attributes,
+/// selecting the decrypter and verifier from the key set based on the `kid`
+/// headers of the outer jwe and inner jws respectively.
+pub fn decrypt_and_verify_auth_result_with_keyset(
+    jwe: &str,
+    keyset: &KeySet,
+) -> Result<AuthResult, Error> {
+    decrypt_and_verify_auth_result_with_keyset_and_options(
+        jwe,
+        keyset,
+        &VerificationOptions::default(),
+    )
+}
+
+/// Decrypt and verify a given jwe like
+/// `decrypt_and_verify_auth_result_with_keyset`, using the given verification
+/// options.
+pub fn decrypt_and_verify_auth_result_with_keyset_and_options(
+    jwe: &str,
+    keyset: &KeySet,
+    options: &VerificationOptions,
+) -> Result<AuthResult, Error> {
+    check_token_size(jwe, options)?;
+    let decrypter = keyset.decrypter(peek_jwe_header(jwe)?.key_id())?;
+    let jws = decrypt_checked(jwe, decrypter, options)?;
+    let verifier = keyset.verifier(peek_jws_header(&jws)?.key_id())?;
+    verify_nested_jws(&jws, verifier, true, options)
+}
+
+/// Decrypt and verify a given jwe to extract the contained attributes, using
+/// the verifier of the issuer named in the `iss` claim of the nested jws. The
+/// issuer is required to match that claim; tokens from unknown issuers are
+/// rejected with `Error::IssuerMismatch`.
+pub fn decrypt_and_verify_auth_result_federated(
+    jwe: &str,
+    decrypter: &dyn JweDecrypter,
+    verifiers_by_issuer: &HashMap<String, Box<dyn JwsVerifier>>,
+) -> Result<AuthResult, Error> {
+    check_token_size(jwe, &VerificationOptions::default())?;
+    let jws = decrypt_nested_jws(jwe, decrypter)?;
+    let issuer = peek_jws_payload(&jws)?
+        .issuer()
+        .ok_or(Error::MissingClaim("iss"))?
+        .to_string();
+    let verifier = verifiers_by_issuer
+        .get(&issuer)
+        .ok_or(Error::IssuerMismatch)?;
+    let options = VerificationOptions {
+        expected_issuer: Some(issuer),
+        ..VerificationOptions::default()
+    };
+    verify_nested_jws(&jws, verifier.as_ref(), true, &options)
+}
+
+/// Decrypt and verify a given jwe to extract the contained attributes, trying
+/// each of the given decrypters and verifiers in order until one matches. This
+/// allows tokens made with several generations of keys to be accepted without
+/// relying on key ids. When no decrypter or verifier matches, the errors of all
+/// attempts are returned in `Error::NoMatchingKey`.
+pub fn decrypt_and_verify_auth_result_multi(
+    jwe: &str,
+    verifiers: &[&dyn JwsVerifier],
+    decrypters: &[&dyn JweDecrypter],
+) -> Result<AuthResult, Error> {
+    let options = VerificationOptions::default();
+    check_token_size(jwe, &options)?;
+    let jws = try_each(decrypters, |decrypter| decrypt_nested_jws(jwe, *decrypter))?;
+    try_each(verifiers, |verifier| {
+        verify_nested_jws(&jws, *verifier, true, &options)
+    })
+}
+
+/// Decrypt and verify a given jwe signed with a shared secret that is being
+/// rotated, accepting tokens signed with either the primary or the previous
+/// secret during the overlap. The previous secret is also tried when the token
+/// uses a different algorithm than the primary one, so that the rotation may
+/// change algorithm as well. When neither verifies, `Error::VerifyFailed` is
+/// returned regardless of which algorithms the secrets use.
+pub fn decrypt_and_verify_auth_result_rotating(
+    jwe: &str,
+    primary: &dyn JwsVerifier,
+    previous: &dyn JwsVerifier,
+    decrypter: &dyn JweDecrypter,
+) -> Result<AuthResult, Error> {
+    let options = VerificationOptions::default();
+    check_token_size(jwe, &options)?;
+    let jws = decrypt_nested_jws(jwe, decrypter)?;
+    match verify_nested_jws(&jws, primary, true, &options) {
+        Err(Error::VerifyFailed(_) | Error::AlgorithmMismatch) => {
+            match verify_nested_jws(&jws, previous, true, &options) {
+                Err(Error::AlgorithmMismatch) => Err(Error::VerifyFailed(
+                    JoseError::InvalidSignature(anyhow::anyhow!(
+                        "token is signed by neither the primary nor the previous secret"
+                    )),
+                )),
+                result => result,
+            }
+        }
+        result => result,
+    }
+}
+
+// Return the result of the first key for which the operation does not fail
+// because of a mismatching key. Other errors, such as an expired token, are
+// returned immediately, as they occur only after the key was found to match.
+fn try_each<K, T>(keys: &[K], operation: impl Fn(&K) -> Result<T, Error>) -> Result<T, Error> {
+    let mut errors = vec![];
+    for key in keys {
+        match operation(key) {
+            Err(
+                e @ (Error::DecryptFailed(_) | Error::VerifyFailed(_) | Error::AlgorithmMismatch),
+            ) => errors.push(e),
+            result => return result,
+        }
+    }
+    Err(Error::NoMatchingKey(errors))
+}
@@ -0,0 +1,60 @@
+use std::{future::Future, sync::Arc};
+
+use josekit::{
+    jwe::{JweDecrypter, JweEncrypter},
+    jws::{JwsSigner, JwsVerifier},
+};
+use tokio::task::JoinError;
+use verder_helpen_proto::AuthResult;
+
+use crate::{
+    error::Error,
+    jwt::{decrypt_and_verify_auth_result, sign_and_encrypt_auth_result},
+};
+
+// Async wrappers
+//
+// The cryptographic operations are CPU bound, so these run them on tokio's
+// blocking thread pool to avoid stalling the async executor. Because such
+// tasks must be 'static, all inputs are taken by value, with keys shared
+// through an Arc.
+
+async fn run_blocking<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T, Error> + Send + 'static,
+) -> Result<T, Error> {
+    task_result(tokio::task::spawn_blocking(f).await)
+}
+
+// Unwrap the outcome of a blocking task, propagating its panic if it panicked.
+// A task cancelled because the runtime is shutting down is reported as
+// `Error::Cancelled` instead.
+pub(crate) fn task_result<T>(joined: Result<Result<T, Error>, JoinError>) -> Result<T, Error> {
+    match joined {
+        Ok(result) => result,
+        Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+        Err(_) => Err(Error::Cancelled),
+    }
+}
+
+/// Sign and encrypt a given set of attributes on the blocking thread pool.
+pub fn sign_and_encrypt_auth_result_async(
+    auth_result: AuthResult,
+    signer: Arc<dyn JwsSigner>,
+    encrypter: Arc<dyn JweEncrypter>,
+) -> impl Future<Output = Result<String, Error>> {
+    run_blocking(move || {
+        sign_and_encrypt_auth_result(&auth_result, signer.as_ref(), encrypter.as_ref())
+    })
+}
+
+/// Decrypt and verify a given jwe to extract the contained attributes on the
+/// blocking thread pool.
+pub fn decrypt_and_verify_auth_result_async(
+    jwe: String,
+    validator: Arc<dyn JwsVerifier>,
+    decrypter: Arc<dyn JweDecrypter>,
+) -> impl Future<Output = Result<AuthResult, Error>> {
+    run_blocking(move || {
+        decrypt_and_verify_auth_result(&jwe, validator.as_ref(), decrypter.as_ref())
+    })
+}
@@ -0,0 +1,74 @@
+#[cfg(feature = "observer")]
+use std::{
+    sync::{Arc, PoisonError, RwLock},
+    time::{Duration, Instant},
+};
+
+use crate::error::Error;
+
+// Observation hooks
+//
+
+/// Receiver of events about the operations performed by this library, for
+/// instance to collect metrics. All methods do nothing by default.
+#[cfg(feature = "observer")]
+pub trait Observer: Send + Sync {
+    /// Called after signing a jws.
+    fn on_sign(&self, _duration: Duration, _success: bool) {}
+    /// Called after encrypting a jwe.
+    fn on_encrypt(&self, _duration: Duration, _success: bool) {}
+    /// Called after decrypting a jwe.
+    fn on_decrypt(&self, _duration: Duration, _success: bool) {}
+    /// Called after verifying a jws and its claims.
+    fn on_verify(&self, _duration: Duration, _success: bool) {}
+}
+
+#[cfg(feature = "observer")]
+static OBSERVER: RwLock<Option<Arc<dyn Observer>>> = RwLock::new(None);
+
+/// Install the observer notified of all subsequent operations, replacing any
+/// previously installed observer.
+#[cfg(feature = "observer")]
+pub fn set_observer(observer: Arc<dyn Observer>) {
+    *OBSERVER.write().unwrap_or_else(PoisonError::into_inner) = Some(observer);
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Operation {
+    Sign,
+    Encrypt,
+    Decrypt,
+    Verify,
+}
+
+// Perform an operation, notifying the installed observer (if any) afterwards.
+#[cfg(feature = "observer")]
+pub(crate) fn observe<T>(
+    operation: Operation,
+    perform: impl FnOnce() -> Result<T, Error>,
+) -> Result<T, Error> {
+    let start = Instant::now();
+    let result = perform();
+    let observer = OBSERVER
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    if let Some(observer) = observer {
+        let (duration, success) = (start.elapsed(), result.is_ok());
+        match operation {
+            Operation::Sign => observer.on_sign(duration, success),
+            Operation::Encrypt => observer.on_encrypt(duration, success),
+            Operation::Decrypt => observer.on_decrypt(duration, success),
+            Operation::Verify => observer.on_verify(duration, success),
+        }
+    }
+    result
+}
+
+#[cfg(not(feature = "observer"))]
+pub(crate) fn observe<T>(
+    _operation: Operation,
+    perform: impl FnOnce() -> Result<T, Error>,
+) -> Result<T, Error> {
+    perform()
+}
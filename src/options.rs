@@ -0,0 +1,183 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime},
+};
+
+use serde_json::{Map, Value};
+
+use crate::status::SuccessSpelling;
+
+// Signing options
+//
+
+/// Lifetime of tokens produced with the default signing options.
+pub const DEFAULT_LIFETIME: Duration = Duration::from_secs(5 * 60);
+
+/// Content encryption algorithm used with the default signing options.
+pub const DEFAULT_CONTENT_ENCRYPTION: &str = "A128CBC-HS256";
+
+/// Name of the claim of the outer jwe holding the nested jws with the default
+/// signing and verification options.
+pub const DEFAULT_NESTED_CLAIM: &str = "njwt";
+
+/// Token type (`typ`) and content type (`cty`) of the outer jwe with the
+/// default signing options.
+pub const DEFAULT_TOKEN_TYPE: &str = "JWT";
+
+/// Size in bytes above which the nested jws is compressed with the default
+/// signing options, when compression is enabled.
+pub const DEFAULT_COMPRESSION_THRESHOLD: usize = 1024;
+
+/// Serialization used for the outer jwe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Serialization {
+    /// Compact serialization, five base64 segments separated by dots.
+    #[default]
+    Compact,
+    /// Flattened json serialization.
+    Json,
+}
+
+/// Options controlling the token produced when signing and encrypting.
+#[derive(Debug, Clone)]
+pub struct SignOptions {
+    /// Amount of time for which the token is valid.
+    pub lifetime: Duration,
+    /// Content encryption algorithm (`enc`) used for the outer jwe.
+    pub content_encryption: String,
+    /// Audience (`aud`) the token is intended for, if any.
+    pub audience: Option<String>,
+    /// Issuer (`iss`) of the token, if any.
+    pub issuer: Option<String>,
+    /// Key id (`kid`) placed in the jws header, overriding that of the signer.
+    pub kid: Option<String>,
+    /// Moment (`nbf`) before which the token is not valid, if any.
+    pub not_before: Option<SystemTime>,
+    /// Compress the nested jws using DEFLATE (`zip: DEF`) before encrypting,
+    /// when it is larger than `compression_threshold`.
+    pub compress: bool,
+    /// Size in bytes the nested jws must exceed to be compressed, as
+    /// compressing small tokens gains little or even grows them.
+    pub compression_threshold: usize,
+    /// Additional claims placed in the signed payload. These may not include
+    /// claims set by this library itself, such as `exp` or `status`.
+    pub extra_claims: Map<String, Value>,
+    /// Serialization used for the outer jwe, compact by default.
+    pub serialization: Serialization,
+    /// Moment the token is issued (`iat`), from which its expiry is computed.
+    /// Defaults to the current time.
+    pub issued_at: Option<SystemTime>,
+    /// Unique id (`jti`) of the token, if any.
+    pub jwt_id: Option<String>,
+    /// Give the token a random uuid as id (`jti`), unless `jwt_id` is set.
+    #[cfg(feature = "uuid")]
+    pub random_jwt_id: bool,
+    /// Token type (`typ`) placed in the jwe header.
+    pub token_type: String,
+    /// Content type (`cty`) placed in the jwe header.
+    pub content_type: String,
+    /// Name of the claim of the outer jwe holding the nested jws.
+    pub nested_claim: String,
+    /// Spelling of the successful status, the current one by default.
+    pub success_spelling: SuccessSpelling,
+    /// Challenge (`nonce`) echoed in the token, binding it to a request.
+    pub nonce: Option<String>,
+    /// Status of individual attributes, such as whether each was verified,
+    /// placed in the `attribute_status` claim when not empty.
+    pub attribute_status: HashMap<String, String>,
+    /// Additional parameters placed in the protected jwe header, which is not
+    /// encrypted. These may not include registered jwe header parameters, such
+    /// as `alg`, `enc` or `crit`.
+    pub extra_headers: HashMap<String, String>,
+}
+
+impl Default for SignOptions {
+    fn default() -> Self {
+        SignOptions {
+            lifetime: DEFAULT_LIFETIME,
+            content_encryption: DEFAULT_CONTENT_ENCRYPTION.to_string(),
+            audience: None,
+            issuer: None,
+            kid: None,
+            not_before: None,
+            compress: false,
+            compression_threshold: DEFAULT_COMPRESSION_THRESHOLD,
+            extra_claims: Map::new(),
+            serialization: Serialization::Compact,
+            issued_at: None,
+            jwt_id: None,
+            #[cfg(feature = "uuid")]
+            random_jwt_id: false,
+            token_type: DEFAULT_TOKEN_TYPE.to_string(),
+            content_type: DEFAULT_TOKEN_TYPE.to_string(),
+            nested_claim: DEFAULT_NESTED_CLAIM.to_string(),
+            success_spelling: SuccessSpelling::Current,
+            nonce: None,
+            attribute_status: HashMap::new(),
+            extra_headers: HashMap::new(),
+        }
+    }
+}
+
+// Verification options
+//
+
+/// Maximum size in bytes of tokens accepted with the default verification
+/// options.
+pub const DEFAULT_MAX_TOKEN_SIZE: usize = 1024 * 1024;
+
+/// Options controlling the checks performed when decrypting and verifying a
+/// token.
+#[derive(Debug, Clone)]
+pub struct VerificationOptions {
+    /// Amount of clock skew tolerated when checking the `exp`, `nbf` and `iat`
+    /// claims. Defaults to zero.
+    pub leeway: Duration,
+    /// Audience the token must be intended for. When not set, the `aud` claim
+    /// is not checked.
+    pub expected_audience: Option<String>,
+    /// Issuer the token must originate from. When not set, the `iss` claim is
+    /// not checked.
+    pub expected_issuer: Option<String>,
+    /// Accept tokens regardless of their `sub` claim. By default, only tokens
+    /// with the subject used for auth results are accepted.
+    pub skip_subject_check: bool,
+    /// Content encryption algorithms (`enc`) accepted for the outer jwe. When
+    /// not set, any algorithm supported by josekit is accepted.
+    pub allowed_content_encryption: Option<Vec<String>>,
+    /// Moment against which the time claims are checked. Defaults to the
+    /// current time.
+    pub now: Option<SystemTime>,
+    /// Maximum size in bytes of accepted tokens, checked before any parsing.
+    pub max_token_size: usize,
+    /// Content type (`cty`) the jwe header must carry. When not set, the
+    /// `cty` header is not checked.
+    pub expected_content_type: Option<String>,
+    /// Maximum age of accepted tokens, as measured from their `iat` claim,
+    /// regardless of their `exp` claim. When set, tokens without `iat` are
+    /// rejected.
+    pub max_age: Option<Duration>,
+    /// Name of the claim of the outer jwe holding the nested jws.
+    pub nested_claim: String,
+    /// Challenge the `nonce` claim of the token must echo. When not set, the
+    /// `nonce` claim is not checked.
+    pub expected_nonce: Option<String>,
+}
+
+impl Default for VerificationOptions {
+    fn default() -> Self {
+        VerificationOptions {
+            leeway: Duration::ZERO,
+            expected_audience: None,
+            expected_issuer: None,
+            skip_subject_check: false,
+            allowed_content_encryption: None,
+            now: None,
+            max_token_size: DEFAULT_MAX_TOKEN_SIZE,
+            expected_content_type: None,
+            max_age: None,
+            nested_claim: DEFAULT_NESTED_CLAIM.to_string(),
+            expected_nonce: None,
+        }
+    }
+}
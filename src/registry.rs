@@ -0,0 +1,107 @@
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    io::Write,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use josekit::{
+    jwe::{JweDecrypter, JweEncrypter},
+    jws::{JwsSigner, JwsVerifier},
+};
+use openssl::sha::Sha256;
+use serde::Serialize;
+
+use crate::{
+    config::{EncryptionKeyConfig, SignKeyConfig},
+    error::Error,
+};
+
+// Key registry
+//
+
+// Keys are cached by the SHA-256 digest of their serialized configuration, so
+// that the registry does not hold on to copies of the key material. The
+// configuration is serialized straight into the hash, so that no buffer holding
+// the key material is left behind either.
+type Digest = [u8; 32];
+type Cache<K> = Mutex<HashMap<Digest, Arc<K>>>;
+
+/// Cache of keys built from configurations, so that loading the same
+/// configuration again reuses the key built before rather than parsing the key
+/// material anew. Keys given as `key_file` are not reloaded when the file
+/// changes.
+#[derive(Default)]
+pub struct KeyRegistry {
+    signers: Cache<dyn JwsSigner>,
+    verifiers: Cache<dyn JwsVerifier>,
+    encrypters: Cache<dyn JweEncrypter>,
+    decrypters: Cache<dyn JweDecrypter>,
+}
+
+impl KeyRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the signer for the given configuration, building it when needed.
+    pub fn signer(&self, config: &SignKeyConfig) -> Result<Arc<dyn JwsSigner>, Error> {
+        cached(&self.signers, config, Arc::try_from)
+    }
+
+    /// Get the verifier for the given configuration, building it when needed.
+    pub fn verifier(&self, config: &SignKeyConfig) -> Result<Arc<dyn JwsVerifier>, Error> {
+        cached(&self.verifiers, config, Arc::try_from)
+    }
+
+    /// Get the encrypter for the given configuration, building it when needed.
+    pub fn encrypter(&self, config: &EncryptionKeyConfig) -> Result<Arc<dyn JweEncrypter>, Error> {
+        cached(&self.encrypters, config, Arc::try_from)
+    }
+
+    /// Get the decrypter for the given configuration, building it when needed.
+    pub fn decrypter(&self, config: &EncryptionKeyConfig) -> Result<Arc<dyn JweDecrypter>, Error> {
+        cached(&self.decrypters, config, Arc::try_from)
+    }
+}
+
+// Look up the key for a configuration, building and caching it when missing.
+// Keys are built without holding the lock, so that a slow load does not block
+// other lookups. When the same key is built concurrently, the first one cached
+// is kept. Configurations that fail to build are not cached.
+fn cached<C: Serialize + Clone, K: ?Sized>(
+    cache: &Cache<K>,
+    config: &C,
+    build: impl FnOnce(C) -> Result<Arc<K>, Error>,
+) -> Result<Arc<K>, Error> {
+    let digest = digest(config)?;
+    if let Some(key) = lock(cache).get(&digest) {
+        return Ok(key.clone());
+    }
+    let key = build(config.clone())?;
+    Ok(lock(cache).entry(digest).or_insert(key).clone())
+}
+
+fn lock<K: ?Sized>(cache: &Cache<K>) -> MutexGuard<'_, HashMap<Digest, Arc<K>>> {
+    cache.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+// Hash the serialized form of a configuration.
+fn digest(config: &impl Serialize) -> Result<Digest, Error> {
+    let mut hasher = HashWriter(Sha256::new());
+    serde_json::to_writer(&mut hasher, config)?;
+    Ok(hasher.0.finish())
+}
+
+struct HashWriter(Sha256);
+
+impl Write for HashWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+use verder_helpen_proto::{AuthResult, AuthStatus};
+
+use crate::error::Error;
+
+// Status checks
+//
+
+/// Convenience checks on the status of an auth result.
+pub trait AuthResultExt {
+    /// Successful auth result carrying the given attributes, for callers of the
+    /// attribute functions that need the auth result envelope.
+    fn from_attributes(attributes: HashMap<String, String>) -> Self
+    where
+        Self: Sized;
+    /// Whether the authentication succeeded.
+    fn is_success(&self) -> bool;
+    /// Whether the authentication failed.
+    fn is_failure(&self) -> bool;
+}
+
+impl AuthResultExt for AuthResult {
+    fn from_attributes(attributes: HashMap<String, String>) -> Self {
+        AuthResult {
+            status: AuthStatus::Success,
+            attributes: Some(attributes),
+            session_url: None,
+        }
+    }
+
+    fn is_success(&self) -> bool {
+        matches!(self.status, AuthStatus::Success)
+    }
+
+    fn is_failure(&self) -> bool {
+        matches!(self.status, AuthStatus::Failed)
+    }
+}
+
+// Status spelling
+//
+// Older versions of the proto crate spelled the successful status `Succes`.
+// Tokens with either spelling are accepted, and tokens can be produced with the
+// old spelling for recipients that only know that one.
+
+/// Spelling of the successful status in produced tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SuccessSpelling {
+    /// The spelling of the current proto crate.
+    #[default]
+    Current,
+    /// The misspelled `Succes` of older versions of the proto crate.
+    Legacy,
+}
+
+/// The old spelling of the successful status, as it appears in tokens.
+pub(crate) const LEGACY_SUCCESS: &str = "succes";
+
+// Rewrite a status claim holding the successful status to the old spelling.
+pub(crate) fn legacy_status(status: &Value) -> Result<Value, Error> {
+    if *status == serde_json::to_value(AuthStatus::Success)? {
+        Ok(Value::String(LEGACY_SUCCESS.to_string()))
+    } else {
+        Ok(status.clone())
+    }
+}
+
+// Parse a status claim, accepting the old spelling of the successful status.
+pub(crate) fn parse_status(status: &Value) -> Result<AuthStatus, Error> {
+    serde_json::from_value::<AuthStatus>(status.clone()).or_else(|_| match status.as_str() {
+        Some(LEGACY_SUCCESS) => Ok(AuthStatus::Success),
+        _ => Err(Error::InvalidClaimType("status")),
+    })
+}
@@ -0,0 +1,164 @@
+use std::time::{Duration, SystemTime};
+
+use verder_helpen_proto::{AuthResult, AuthStatus};
+
+use crate::{
+    error::Error, jwt::decrypt_and_verify_auth_result_with_options, options::VerificationOptions,
+    test_keys::TestKeys,
+};
+
+// Test vectors
+//
+// Known inputs and tokens for certifying other implementations, available with
+// the test-keys feature. The tokens are snapshots in the format this library
+// produces, fixed here as regression tests of its decoding; they do not by
+// themselves show interoperability. All supported key management algorithms
+// encrypt with a random content key, so tokens cannot be compared byte for
+// byte. Instead, a token is decrypted and verified with the keys of the vector
+// and its contents compared to the input.
+
+/// Moment at which the tokens of the test vectors are issued.
+const ISSUED_AT: Duration = Duration::from_secs(1_700_000_000);
+
+#[cfg(feature = "rsa")]
+const RSA_SUCCESS_TOKEN: &str = "\
+    eyJ0eXAiOiJKV1QiLCJjdHkiOiJKV1QiLCJlbmMiOiJBMTI4Q0JDLUhTMjU2IiwiYWxnIjoiUlNBLU9BRVAifQ.ukx\
+    m4svS14dB2duTDFN8IqYVbzCG_Kz_c3fzx1dE0ruTqGOOHsrwDeBmok9RelJ30hX3fgawlJSIl0nfydoIseB6Kuobe\
+    6B8JKZsDbNAWrLdYLSwhVTznU4X65W448B0aCHlL3AFTVG_Syv1pAEBnEYeuP85gorpwqZcXCqXAcUkQo7JQAIDUM8\
+    iljcLDETxlZbHdXU0l0YaO66_Bv1AEkM9f7RePa5d9oyVMaingdG1cwIyCO8uBKPKXsMCchO3iQ3nm9-2pe4S0WHMe\
+    vo9T519SujAw6b_Lb9Dvq4U2RBiHf1Mrsb8Vj05RNs6Ikn0vkep6bJGZFa_MpXZUDEoRA.eyquDxVZNlLR77djLCBU\
+    -g.rbeDLyf3WgNAAMqXKqN4i7NV7YIu6HKvrL-Ies4ee-1COcX6MyyX9DsiiQwQSN5ZEYEsZLEleLvnAaHeUdrG9na\
+    FDUOwvXTGvB8D3BrpwDf0GnDNg1HjqEDkdlh4soIoU5LTbW77ClTyHzdwiwXQPKrQiFqcg5XQnzh3oZxXsGC5gH7ef\
+    4PzCtFAeo_cqp6zU-5m61Fjn4JCGaQnKvjW-PS_ETbr8qVhCPE3gDUn8kgkQIwhIXnjW7b0t3rDf63iyW4xtvypoLp\
+    Gn2jmymGFcpYD4viA0OwTpFOevVd_m3Zz9N1Z9qoKDFaNLExO8Rur61085uP5XCeMpIGMn15oEDYoLFilQHKb7vNEh\
+    GB4w2oq3iHO1YWd5PNnNUdPM4MSzUpgZTpoZEUCpqZX-YtTZFw9sHcCRUmWlVFYDCkGHJUn9ujTeqVu14CjbBRaFCf\
+    UDYXl9mL0lPGCLlvLmNWl9otemTGCIwMpt2tdtd0I0cpZ0WMh96ql7Nef8puVvKN81dNh27Ee95mW0pGvSWdzsi-Fh\
+    JJeW9QrbrDaxHXGxt9ONBzAMLpxE4_YScngZCysPC-FQmYP9U7yyGrhH74uPs9r9VdF2z-OUEDOUv3kvfexoBCjIGP\
+    4u_wDOYhLonzaoRbTNrH22ns7yeGA25lZySvsSEA2hGego4CrbnzrZv0.bra6ayUNoarWIGv1Skcqbw";
+
+#[cfg(feature = "rsa")]
+const RSA_ATTRIBUTES_TOKEN: &str = "\
+    eyJ0eXAiOiJKV1QiLCJjdHkiOiJKV1QiLCJlbmMiOiJBMTI4Q0JDLUhTMjU2IiwiYWxnIjoiUlNBLU9BRVAifQ.mqS\
+    lSBqCGhc1KW13-tRPju4cU8w6YFkQxUdTOFD64aJyDWGwIMqPhLajXUW8TGFByWCQL-trgnUFJ6PglMBznPKxGjMPO\
+    _iyrL9_PTXH55TUGm9T8MKIRBenVZDfevnqWwo8Q4z9PRBsVn8ggwbW3ovruMOVokm_XFhdyuiRIQVqiyzhvgHxTKD\
+    JUf2dyOVhPInMIVxL7yjUZ12n08647dwGcDUD0_k_8j-rl5Ho4U0J_2a4YdJXsjG26CTSu9NtZb8DqeLIyKj_jqJut\
+    Cd1y5q6bMOAUx73wRYV_FM44ZCtAsD8b6NQ_DmCY4u87xFJg7TTBNDkxTg6-kzIrFeQ9w.kKm0y31alRrEPwZyVq6u\
+    0g.C-lM3J3nliTa1pBkeIeJ465AmTMC5zIS9-oWEHO79gwx1Zw2YJHgPLBXM5KNFuPE5bJaVtiypBqBYBVn-OM6lXi\
+    AftYAQu-IkUsoB-rDogg2EFPdfb4HJOZbX1_CMuO4-ac-DcFS-RrI2P42_h__6DMklB_eRfTuw3VVs6y6xp2Bl550P\
+    HFXi3cRMHT1rEkTcgIywf3RlP9qi29wj9XA4F6a81YvCw6iG_UVaQFOxrMAK2rM8vx-zBFmcfHgG9RDZHp8VvQS2F7\
+    UtDd8WkJ4XzoJiD8pq0_6ZSBkNXT6sasarPJq3-jUNaxfdMpvjI6Mv-Rxs-7P784dogpvJAu-LtWqHydbvC3nl73J6\
+    H0SwW5dgmKz9x_l0EWOIFT7GgxIiGLcsshHPE7y5GLsXICBRaWzHkjltBlJoQIJpk9UvgfzOUQn56toE2UUQFKRsdW\
+    vNjdg2kiOly78GAPDxsHTUX_lFEgTl21c4jJS6o6fN7x15wD7SmCF5r-U3wUpUbpotiHuWJlR0p8bn5ccweG7HlpKw\
+    FMDe_awoq-0sTPNplDUhgQzSgpSWKu4zcnSTBBXNo7oViWOZsW5cvBGFxVWxysu7miev3OhMNqqbqH95RpBOz3-P-i\
+    _4kiFDPCo7Y2mlf1NX4qosa92KMMGwsr2vhe9EVEVHiq_NUhzVNaP6KVrklMieFvI7dH0wt7nmoSGwS3mAhfsqr124\
+    Fw0S4ulGvg0V6UZVFBRa5MBW3qe46rUQdAvHSvHWXh5zgsvrFMpau2AebVljw9eKktDJC9lOV2rwMX0iRECkdZklFc\
+    rFc6ZWxZQ3LEAp7Q2XOHBo3jGybdg0VkEL9PuVaJSWa7GXXKcPpQfR552CsHidAwd8E8zD0O1VNAIrdiKOAgx34o3.\
+    TxAePX58u0_6uq2nTAGj5Q";
+
+#[cfg(feature = "ec")]
+const EC_FAILED_TOKEN: &str = "\
+    eyJ0eXAiOiJKV1QiLCJjdHkiOiJKV1QiLCJlbmMiOiJBMTI4Q0JDLUhTMjU2IiwiYWxnIjoiRUNESC1FUyIsImVway\
+    I6eyJrdHkiOiJFQyIsImNydiI6IlAtMjU2IiwieCI6IlBrb2Z5STFySWEteUtZSFpZTnI1ZjYtb2l5SXNkeU9PNjVV\
+    MU05c1JQbG8iLCJ5IjoiN3RDM0l0Nm9XTGthVE1neUNiVmZUWGRSRE4yS0I4cVZXeWRBd0F5Z05rayJ9fQ..yDlSAb\
+    2wuXz-th4_4eYRhg.1o_uLw9n-7-u0xdb_5rc7XuL1KXhC1-W_j9w3Vwnt-5xQYcxyUWaoWg5KklHiO-UlDX2P5RZV\
+    KoQUL5uRlqq6Hgf_SPfTbNtSgbSmY7YbbjWBw3z99Gywek3aAMNlTSJLOSlIUoqdVsmqIGDNY650zxSBiYcMMturM3\
+    AE7Vc7m1_m44WCvRm2XS-WpCLJoZCYEF4mggiqaQtchYcnAijL9b2YVaiwpMphjpfr068EWD7z5lDK5Z1Xx-QWpiKc\
+    HTPhxXTeZCSsyF1LHdNALQxDtOY6vk3z2u3Owb30azCHEKcp2uSQ6vKnS_A-AHggIJOARRHzUOZnHpOubaykp5pfg.\
+    ivd0cVK0EHwwLY96IFlBZg";
+
+/// Auth result to be encoded, the keys to use and the resulting token.
+pub struct TestVector {
+    /// Short description of the vector.
+    pub name: &'static str,
+    /// Auth result encoded in the token.
+    pub auth_result: AuthResult,
+    /// Keys the token is signed and encrypted with.
+    pub keys: fn() -> TestKeys,
+    /// Moment the token is issued (`iat`), with the default lifetime.
+    pub issued_at: SystemTime,
+    /// Token encoding the auth result.
+    pub token: &'static str,
+}
+
+fn vector(
+    name: &'static str,
+    auth_result: AuthResult,
+    keys: fn() -> TestKeys,
+    token: &'static str,
+) -> TestVector {
+    TestVector {
+        name,
+        auth_result,
+        keys,
+        issued_at: SystemTime::UNIX_EPOCH + ISSUED_AT,
+        token,
+    }
+}
+
+/// The built-in test vectors.
+pub fn test_vectors() -> Vec<TestVector> {
+    vec![
+        #[cfg(feature = "rsa")]
+        vector(
+            "rsa-success",
+            AuthResult {
+                status: AuthStatus::Success,
+                attributes: None,
+                session_url: None,
+            },
+            crate::test_keys::test_rsa_keys,
+            RSA_SUCCESS_TOKEN,
+        ),
+        #[cfg(feature = "rsa")]
+        vector(
+            "rsa-attributes",
+            AuthResult {
+                status: AuthStatus::Success,
+                attributes: Some(
+                    [
+                        ("email", "hd@example.com"),
+                        ("name", "Henk Dieter"),
+                        ("age", "42"),
+                    ]
+                    .iter()
+                    .map(|(key, value)| (key.to_string(), value.to_string()))
+                    .collect(),
+                ),
+                session_url: Some("https://example.com/session".to_string()),
+            },
+            crate::test_keys::test_rsa_keys,
+            RSA_ATTRIBUTES_TOKEN,
+        ),
+        #[cfg(feature = "ec")]
+        vector(
+            "ec-failed",
+            AuthResult {
+                status: AuthStatus::Failed,
+                attributes: None,
+                session_url: None,
+            },
+            crate::test_keys::test_ec_keys,
+            EC_FAILED_TOKEN,
+        ),
+    ]
+}
+
+/// Check a token for the given vector, by decrypting and verifying it with the
+/// keys of the vector and comparing the contained auth result. This checks
+/// both the token of the vector itself and tokens produced by another
+/// implementation from its input. Returns whether the auth results match;
+/// tokens that fail to decrypt or verify give an error.
+pub fn verify_vector(vector: &TestVector, token: &str) -> Result<bool, Error> {
+    let (_, verifier, _, decrypter) = (vector.keys)();
+    let options = VerificationOptions {
+        now: Some(vector.issued_at),
+        ..VerificationOptions::default()
+    };
+    let auth_result = decrypt_and_verify_auth_result_with_options(
+        token,
+        verifier.as_ref(),
+        decrypter.as_ref(),
+        &options,
+    )?;
+    Ok(auth_result == vector.auth_result)
+}
@@ -0,0 +1,38 @@
+use crate::error::Error;
+
+// Tracing of verification decisions
+//
+// Only the names of the steps and the reason of a rejection are recorded,
+// never the values of the claims involved.
+
+// Record that a validation step succeeded.
+#[cfg(feature = "tracing")]
+pub(crate) fn step_ok(step: &'static str) {
+    tracing::debug!(step, ok = true, "{} ok", step);
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn step_ok(_step: &'static str) {}
+
+// Perform a verification within a span of the given name, warning when the
+// token is rejected.
+#[cfg(feature = "tracing")]
+pub(crate) fn traced<T>(
+    name: &'static str,
+    perform: impl FnOnce() -> Result<T, Error>,
+) -> Result<T, Error> {
+    let _span = tracing::debug_span!("verification", operation = name).entered();
+    let result = perform();
+    if let Err(e) = &result {
+        tracing::warn!(ok = false, category = ?e.category(), reason = %e, "token rejected");
+    }
+    result
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn traced<T>(
+    _name: &'static str,
+    perform: impl FnOnce() -> Result<T, Error>,
+) -> Result<T, Error> {
+    perform()
+}